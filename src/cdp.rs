@@ -1,12 +1,18 @@
 use anyhow::{Context, Result, anyhow};
 use futures::{SinkExt, StreamExt};
 use serde::Deserialize;
-use std::process::{Command, Stdio};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
 use tokio_tungstenite::tungstenite::Message;
 
+use crate::errors::ErrorKind;
+
 #[cfg(unix)]
 unsafe extern "C" {
     fn setsid() -> i32;
+    fn geteuid() -> u32;
 }
 
 #[derive(Deserialize)]
@@ -19,43 +25,254 @@ pub struct TargetJson {
     pub webSocketDebuggerUrl: Option<String>,
 }
 
+/// Chrome launch settings from global CLI flags
+pub struct LaunchOptions {
+    /// How long to wait for a freshly launched Chrome to answer on the CDP port
+    pub timeout_ms: u64,
+    /// Launch Chrome with `--no-sandbox` even when not running as root or in a container
+    pub no_sandbox: bool,
+    /// Extra attempts at the `/json` target list after a transient HTTP failure
+    pub connect_retries: u32,
+    /// Named persistent profile to launch with instead of the per-port temp dir
+    pub profile: Option<String>,
+    /// Accept invalid TLS certificates, at launch and on every connection
+    pub ignore_https_errors: bool,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 5000,
+            no_sandbox: false,
+            connect_retries: 2,
+            profile: None,
+            ignore_https_errors: false,
+        }
+    }
+}
+
+static LAUNCH_OPTIONS: OnceLock<LaunchOptions> = OnceLock::new();
+
+/// Set launch settings once at startup; later calls are ignored.
+pub fn set_launch_options(options: LaunchOptions) {
+    let _ = LAUNCH_OPTIONS.set(options);
+}
+
+fn launch_options() -> &'static LaunchOptions {
+    LAUNCH_OPTIONS.get_or_init(LaunchOptions::default)
+}
+
+static BYPASS_CSP: OnceLock<bool> = OnceLock::new();
+
+/// Have every page connection made by this process ignore Content-Security-Policy.
+pub fn set_bypass_csp(enabled: bool) {
+    let _ = BYPASS_CSP.set(enabled);
+}
+
+static DUMP_PROTOCOL: OnceLock<bool> = OnceLock::new();
+
+/// Print every command sent by this process with its raw result to stderr (`--dump-protocol`).
+pub fn set_dump_protocol(enabled: bool) {
+    let _ = DUMP_PROTOCOL.set(enabled);
+}
+
+pub(crate) fn dump_protocol() -> bool {
+    DUMP_PROTOCOL.get() == Some(&true)
+}
+
+/// One `--dump-protocol` line: the method, its params and the raw `result`
+/// (or `error`) from the response.
+pub(crate) fn protocol_dump_line(
+    method: &str,
+    params: &serde_json::Value,
+    response: &serde_json::Value,
+) -> serde_json::Value {
+    let mut line = serde_json::json!({ "method": method, "params": params });
+    for key in ["result", "error"] {
+        if let Some(value) = response.get(key) {
+            line[key] = value.clone();
+        }
+    }
+    line
+}
+
+/// Reconnects allowed per command before a dropped socket is reported as an error
+const MAX_RECONNECT_ATTEMPTS: u32 = 1;
+
+/// The websocket closed before a response arrived
+#[derive(Debug)]
+pub(crate) struct ConnectionClosed;
+
+impl std::fmt::Display for ConnectionClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No response from CDP (connection closed)")
+    }
+}
+
+impl std::error::Error for ConnectionClosed {}
+
 pub struct CdpConnection {
     ws: tokio_tungstenite::WebSocketStream<
         tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
     >,
     next_id: i32,
+    /// Port to re-resolve the active target on when the socket drops
+    reconnect_port: Option<u16>,
+    /// Events that arrived while `send` waited for its reply, handed out by `recv`
+    events: VecDeque<serde_json::Value>,
 }
 
 impl CdpConnection {
     pub async fn connect(ws_url: &str) -> Result<Self> {
         let (ws, _) = tokio_tungstenite::connect_async(ws_url).await?;
-        Ok(Self { ws, next_id: 1 })
+        let mut cdp = Self {
+            ws,
+            next_id: 1,
+            reconnect_port: None,
+            events: VecDeque::new(),
+        };
+        if BYPASS_CSP.get() == Some(&true) && ws_url.contains("/devtools/page/") {
+            cdp.bypass_csp().await?;
+        }
+        if launch_options().ignore_https_errors {
+            cdp.ignore_certificate_errors().await?;
+        }
+        Ok(cdp)
+    }
+
+    /// Accept self-signed and otherwise invalid certificates while this session
+    /// is attached, for a Chrome that was not launched with the flag.
+    pub async fn ignore_certificate_errors(&mut self) -> Result<()> {
+        self.send(
+            "Security.setIgnoreCertificateErrors",
+            serde_json::json!({ "ignore": true }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stop enforcing the page's Content-Security-Policy while this session is
+    /// attached. Policies are applied when a document loads, so bypassing before
+    /// navigating also keeps the new document free of its CSP afterwards.
+    pub async fn bypass_csp(&mut self) -> Result<()> {
+        self.send("Page.enable", serde_json::json!({})).await?;
+        self.send("Page.setBypassCSP", serde_json::json!({ "enabled": true }))
+            .await?;
+        Ok(())
     }
 
+    /// Send a command, reconnecting to the active target and retrying if the
+    /// socket dropped before the command could be written. Once written, a
+    /// command is never sent again: it may already have run (a click, a
+    /// navigation), so a lost reply is reported instead. Domain state (e.g.
+    /// `Runtime.enable`) does not survive a reconnect.
     pub async fn send(
         &mut self,
         method: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
+        let mut attempts = 0;
+        loop {
+            match self.write_command(method, &params).await {
+                Err(err)
+                    if is_connection_closed(&err)
+                        && self.reconnect_port.is_some()
+                        && attempts < MAX_RECONNECT_ATTEMPTS =>
+                {
+                    attempts += 1;
+                    self.reconnect().await?;
+                }
+                Err(err) => return Err(err),
+                Ok(id) => return self.read_reply(id, method, &params).await,
+            }
+        }
+    }
+
+    /// Send a command on the current socket only, without reconnecting.
+    async fn send_once(
+        &mut self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let id = self.write_command(method, params).await?;
+        self.read_reply(id, method, params).await
+    }
+
+    async fn write_command(&mut self, method: &str, params: &serde_json::Value) -> Result<i32> {
         let id = self.next_id;
         self.next_id += 1;
 
         let msg = serde_json::json!({ "id": id, "method": method, "params": params });
-        self.ws.send(Message::Text(msg.to_string())).await?;
+        self.ws
+            .send(Message::Text(msg.to_string()))
+            .await
+            .map_err(|_| ConnectionClosed)?;
+        Ok(id)
+    }
 
-        while let Some(resp) = self.recv().await? {
+    async fn read_reply(
+        &mut self,
+        id: i32,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        while let Some(resp) = self.read_message().await? {
             if resp.get("id") != Some(&serde_json::json!(id)) {
+                if resp.get("method").is_some() {
+                    self.events.push_back(resp);
+                }
                 continue;
             }
+            // On stderr, so a command's own output on stdout stays parseable.
+            if dump_protocol() {
+                eprintln!("{}", protocol_dump_line(method, params, &resp));
+            }
             if let Some(error) = resp.get("error") {
                 return Err(anyhow!("CDP error: {}", error));
             }
             return Ok(resp.get("result").cloned().unwrap_or(serde_json::json!({})));
         }
-        Err(anyhow!("No response from CDP"))
+        Err(ConnectionClosed.into())
+    }
+
+    async fn reconnect(&mut self) -> Result<()> {
+        let port = self.reconnect_port.context("Reconnect is not enabled")?;
+        eprintln!("CDP connection dropped, reconnecting...");
+        let targets = get_targets(port).await?;
+        let target = find_active_target(&targets)?;
+        let ws_url = target
+            .webSocketDebuggerUrl
+            .as_ref()
+            .context("Active target has no webSocketDebuggerUrl")?;
+        let (ws, _) = tokio_tungstenite::connect_async(ws_url).await?;
+        self.ws = ws;
+        // Plain `send_once` here: a socket that drops again fails this attempt
+        // instead of reconnecting from inside the reconnect.
+        if BYPASS_CSP.get() == Some(&true) {
+            self.send_once("Page.enable", &serde_json::json!({}))
+                .await?;
+            self.send_once("Page.setBypassCSP", &serde_json::json!({ "enabled": true }))
+                .await?;
+        }
+        if launch_options().ignore_https_errors {
+            self.send_once(
+                "Security.setIgnoreCertificateErrors",
+                &serde_json::json!({ "ignore": true }),
+            )
+            .await?;
+        }
+        Ok(())
     }
 
+    /// Next event (or stray reply), starting with any that arrived during `send`.
     pub async fn recv(&mut self) -> Result<Option<serde_json::Value>> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(Some(event));
+        }
+        self.read_message().await
+    }
+
+    async fn read_message(&mut self) -> Result<Option<serde_json::Value>> {
         while let Some(msg) = self.ws.next().await {
             let Ok(Message::Text(text)) = msg else {
                 continue;
@@ -68,16 +285,28 @@ impl CdpConnection {
     }
 
     pub async fn eval(&mut self, expression: &str) -> Result<serde_json::Value> {
-        let result = self
-            .send(
-                "Runtime.evaluate",
-                serde_json::json!({
-                    "expression": expression,
-                    "returnByValue": true
-                }),
-            )
-            .await?;
+        self.eval_in_context(expression, None).await
+    }
 
+    /// Evaluate in a specific execution context (e.g. an isolated world), or the
+    /// page's main world when `context_id` is `None`.
+    pub async fn eval_in_context(
+        &mut self,
+        expression: &str,
+        context_id: Option<i64>,
+    ) -> Result<serde_json::Value> {
+        let mut params = serde_json::json!({
+            "expression": expression,
+            "returnByValue": true
+        });
+        if let Some(id) = context_id {
+            params["contextId"] = serde_json::json!(id);
+        }
+        let result = self.send("Runtime.evaluate", params).await?;
+
+        if let Some(details) = result.get("exceptionDetails") {
+            return Err(anyhow!("{}", exception_message(details)));
+        }
         if let Some(value) = result.get("result").and_then(|r| r.get("value")) {
             Ok(value.clone())
         } else if let Some(desc) = result
@@ -92,6 +321,21 @@ impl CdpConnection {
     }
 }
 
+/// Best human-readable message from `Runtime.evaluate` exception details.
+pub fn exception_message(details: &serde_json::Value) -> String {
+    details
+        .get("exception")
+        .and_then(|e| e.get("description").or_else(|| e.get("value")))
+        .and_then(|d| d.as_str())
+        .or_else(|| details.get("text").and_then(|t| t.as_str()))
+        .unwrap_or("Evaluation failed")
+        .to_string()
+}
+
+pub(crate) fn is_connection_closed(err: &anyhow::Error) -> bool {
+    err.is::<ConnectionClosed>()
+}
+
 fn find_chrome_executable() -> Option<&'static str> {
     const CANDIDATES: &[&str] = &[
         "chromium-browser",
@@ -99,47 +343,147 @@ fn find_chrome_executable() -> Option<&'static str> {
         "google-chrome-stable",
         "google-chrome",
     ];
-    for candidate in CANDIDATES {
-        if Command::new("which")
+    CANDIDATES.iter().copied().find(|candidate| {
+        Command::new("which")
             .arg(candidate)
             .output()
             .map(|o| o.status.success())
             .unwrap_or(false)
-        {
-            return Some(candidate);
-        }
+    })
+}
+
+/// Chrome's sandbox cannot start as root, and containers usually lack the
+/// namespaces it needs, so disable it there even without `--no-sandbox`.
+fn sandbox_unavailable() -> bool {
+    #[cfg(unix)]
+    let is_root = unsafe { geteuid() } == 0;
+    #[cfg(not(unix))]
+    let is_root = false;
+    is_root || std::path::Path::new("/.dockerenv").exists()
+}
+
+fn chrome_log_path(port: u16) -> String {
+    format!("/tmp/browser-cli-chrome-{}.log", port)
+}
+
+/// Last few lines of Chrome's stderr log, for launch failure messages.
+fn chrome_log_tail(port: u16) -> Option<String> {
+    let log = std::fs::read_to_string(chrome_log_path(port)).ok()?;
+    let lines: Vec<&str> = log.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines[lines.len().saturating_sub(5)..].join("\n"))
+}
+
+/// Chrome's user data dir: `~/.cache/browser-cli/profiles/<name>` for `--profile`
+/// (honoring `XDG_CACHE_HOME`), otherwise a throwaway dir per port.
+fn chrome_data_dir(port: u16, profile: Option<&str>) -> Result<PathBuf> {
+    let Some(name) = profile else {
+        return Ok(PathBuf::from(format!("/tmp/browser-cli-chrome-{}", port)));
+    };
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        anyhow::bail!("Invalid profile name '{}'", name);
     }
-    None
+    let cache = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME").context("HOME is not set")?).join(".cache"),
+    };
+    Ok(cache.join("browser-cli").join("profiles").join(name))
 }
 
-fn chrome_launch_args(port: u16) -> Vec<String> {
-    let data_dir = format!("/tmp/browser-cli-chrome-{}", port);
-    vec![
+fn chrome_launch_args(
+    port: u16,
+    data_dir: &Path,
+    no_sandbox: bool,
+    ignore_https_errors: bool,
+) -> Vec<String> {
+    let mut args = vec![
         format!("--remote-debugging-port={}", port),
-        format!("--user-data-dir={}", data_dir),
+        format!("--user-data-dir={}", data_dir.display()),
         "--no-first-run".to_string(),
         "--no-default-browser-check".to_string(),
         // Suppress the "Restore pages? Chrome didn't shut down correctly" bubble
         // that appears when the profile was left dirty by a prior unclean exit.
         "--disable-session-crashed-bubble".to_string(),
         "--hide-crash-restore-bubble".to_string(),
-        "about:blank".to_string(),
-    ]
+    ];
+    if no_sandbox {
+        args.push("--no-sandbox".to_string());
+        args.push("--disable-dev-shm-usage".to_string());
+    }
+    if ignore_https_errors {
+        args.push("--ignore-certificate-errors".to_string());
+    }
+    args.push("about:blank".to_string());
+    args
 }
 
-fn start_chrome(port: u16) -> Result<()> {
-    let chrome = find_chrome_executable().context("Chrome not found in PATH")?;
+fn start_chrome(port: u16) -> Result<Child> {
+    let chrome = find_chrome_executable()
+        .context(ErrorKind::Connection.error("Chrome not found in PATH"))?;
     let mut command = Command::new(chrome);
     detach_from_parent(&mut command);
 
+    // Chrome outlives this process, so its stderr goes to a file rather than a pipe.
+    let log_path = chrome_log_path(port);
+    let log = std::fs::File::create(&log_path)
+        .with_context(|| format!("Failed to create Chrome log {}", log_path))?;
+    let options = launch_options();
+    let data_dir = chrome_data_dir(port, options.profile.as_deref())?;
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create profile dir {}", data_dir.display()))?;
+    let no_sandbox = options.no_sandbox || sandbox_unavailable();
     command
-        .args(chrome_launch_args(port))
+        .args(chrome_launch_args(
+            port,
+            &data_dir,
+            no_sandbox,
+            options.ignore_https_errors,
+        ))
         .stdin(Stdio::null())
         .stdout(Stdio::null())
-        .stderr(Stdio::null())
+        .stderr(log)
         .spawn()
-        .context("Failed to start Chrome")?;
-    Ok(())
+        .context(ErrorKind::Connection.error("Failed to start Chrome"))
+}
+
+/// Poll delays for startup detection: doubling from 50ms, capped at 1s.
+fn startup_backoff(attempt: u32) -> u64 {
+    (50u64 << attempt.min(5)).min(1000)
+}
+
+/// Wait until the launched Chrome answers on `port`, failing early if the process exits.
+async fn wait_for_chrome_startup(port: u16, child: &mut Child, timeout_ms: u64) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+    let mut attempt = 0;
+    loop {
+        if chrome_is_running(port).await {
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait()? {
+            match chrome_log_tail(port) {
+                Some(tail) => anyhow::bail!(ErrorKind::Connection.error(format!(
+                    "Chrome exited during startup ({}):\n{}",
+                    status, tail
+                ))),
+                None => anyhow::bail!(
+                    ErrorKind::Connection
+                        .error(format!("Chrome exited during startup ({})", status))
+                ),
+            }
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            anyhow::bail!(ErrorKind::Connection.error(format!(
+                "Chrome is still starting after {}ms (raise --launch-timeout)",
+                timeout_ms
+            )));
+        }
+        let delay = tokio::time::Duration::from_millis(startup_backoff(attempt));
+        tokio::time::sleep(delay.min(deadline - now)).await;
+        attempt += 1;
+    }
 }
 
 #[cfg(unix)]
@@ -161,18 +505,50 @@ fn detach_from_parent(command: &mut Command) {
 #[cfg(not(unix))]
 fn detach_from_parent(_command: &mut Command) {}
 
-async fn chrome_is_running(port: u16) -> bool {
+pub(crate) async fn chrome_is_running(port: u16) -> bool {
     let url = format!("http://127.0.0.1:{}/json/version", port);
     reqwest::get(&url).await.is_ok()
 }
 
+/// Fetch `/json`, retrying with backoff when Chrome answers with an error status or
+/// drops the request (common right after launch or mid-navigation). A refused
+/// connection means Chrome is not running, so that fails at once.
 async fn get_all_targets(port: u16) -> Result<Vec<TargetJson>> {
     let url = format!("http://127.0.0.1:{}/json", port);
-    let targets: Vec<TargetJson> = reqwest::get(&url)
-        .await
-        .context("Failed to connect to Chrome")?
-        .json()
-        .await?;
+    let retries = launch_options().connect_retries;
+    let mut attempt = 0;
+    let targets: Vec<TargetJson> = loop {
+        let response = reqwest::get(&url).await.and_then(|r| r.error_for_status());
+        let result = match response {
+            Ok(response) => response.json().await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(targets) => break targets,
+            Err(err) if err.is_connect() => {
+                return Err(
+                    anyhow::Error::new(err).context(ErrorKind::Connection.error(format!(
+                        "Failed to connect to Chrome on port {} (connection refused)",
+                        port
+                    ))),
+                );
+            }
+            Err(err) if attempt < retries => {
+                attempt += 1;
+                eprintln!("Chrome target list request failed ({}), retrying...", err);
+                tokio::time::sleep(tokio::time::Duration::from_millis(startup_backoff(attempt)))
+                    .await;
+            }
+            Err(err) => {
+                return Err(
+                    anyhow::Error::new(err).context(ErrorKind::Connection.error(format!(
+                        "Failed to list Chrome targets after {} attempts",
+                        attempt + 1
+                    ))),
+                );
+            }
+        }
+    };
     Ok(targets
         .into_iter()
         .filter(|t| t.r#type == "page" && t.webSocketDebuggerUrl.is_some())
@@ -187,27 +563,18 @@ pub async fn create_new_tab(port: u16, url: &str) -> Result<TargetJson> {
     );
     let target: TargetJson = reqwest::get(&endpoint)
         .await
-        .context("Failed to create new tab")?
+        .context(ErrorKind::Connection.error("Failed to create new tab"))?
         .json()
-        .await?;
+        .await
+        .context(ErrorKind::Connection.error("Invalid /json/new response"))?;
     Ok(target)
 }
 
 pub async fn get_targets(port: u16) -> Result<Vec<TargetJson>> {
     if !chrome_is_running(port).await {
         eprintln!("Starting Chrome with remote debugging on port {}...", port);
-        start_chrome(port)?;
-
-        for _ in 0..50 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            if chrome_is_running(port).await {
-                break;
-            }
-        }
-
-        if !chrome_is_running(port).await {
-            anyhow::bail!("Chrome started but failed to connect after 5 seconds");
-        }
+        let mut child = start_chrome(port)?;
+        wait_for_chrome_startup(port, &mut child, launch_options().timeout_ms).await?;
     }
 
     let mut targets = get_all_targets(port).await?;
@@ -218,7 +585,51 @@ pub async fn get_targets(port: u16) -> Result<Vec<TargetJson>> {
     Ok(targets)
 }
 
+/// Browser metadata from `/json/version` (product, protocol, V8, user agent, websocket URL)
+pub async fn get_version(port: u16) -> Result<serde_json::Value> {
+    get_targets(port).await?;
+    let url = format!("http://127.0.0.1:{}/json/version", port);
+    reqwest::get(&url)
+        .await
+        .context(ErrorKind::Connection.error("Failed to connect to Chrome"))?
+        .json()
+        .await
+        .context(ErrorKind::Connection.error("Invalid /json/version response"))
+}
+
+/// Connect CDP to the browser-level target (for `Target.*` browser context methods)
+pub async fn connect_browser(port: u16) -> Result<CdpConnection> {
+    let version = get_version(port).await?;
+    let ws_url = version
+        .get("webSocketDebuggerUrl")
+        .and_then(|u| u.as_str())
+        .context(ErrorKind::Connection.error("Chrome did not report a browser websocket URL"))?;
+    CdpConnection::connect(ws_url).await
+}
+
+static TARGET_ID: OnceLock<Option<String>> = OnceLock::new();
+
+/// Pin page commands to the target with this id instead of guessing the active page.
+pub fn set_target_id(id: Option<String>) {
+    let _ = TARGET_ID.set(id);
+}
+
+/// The target chosen with `--target-id`, if any; errors when it no longer exists.
+pub fn pinned_target(targets: &[TargetJson]) -> Result<Option<&TargetJson>> {
+    let Some(id) = TARGET_ID.get().and_then(|id| id.as_deref()) else {
+        return Ok(None);
+    };
+    targets
+        .iter()
+        .find(|t| t.id == id)
+        .map(Some)
+        .ok_or_else(|| anyhow!("No target with id {} (see `tabs list --json`)", id))
+}
+
 pub fn find_active_target(targets: &[TargetJson]) -> Result<&TargetJson> {
+    if let Some(target) = pinned_target(targets)? {
+        return Ok(target);
+    }
     targets
         .iter()
         .find(|t| !t.url.starts_with("about:") && !t.url.starts_with("chrome://"))
@@ -230,22 +641,94 @@ pub fn find_active_target(targets: &[TargetJson]) -> Result<&TargetJson> {
 pub async fn connect_active(port: u16) -> Result<CdpConnection> {
     let targets = get_targets(port).await?;
     let target = find_active_target(&targets)?;
-    let ws_url = target.webSocketDebuggerUrl.as_ref().unwrap();
-    CdpConnection::connect(ws_url).await
+    let ws_url = target
+        .webSocketDebuggerUrl
+        .as_ref()
+        .context("Active target has no webSocketDebuggerUrl")?;
+    let mut cdp = CdpConnection::connect(ws_url).await?;
+    cdp.reconnect_port = Some(port);
+    Ok(cdp)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::chrome_launch_args;
+    use super::{
+        ConnectionClosed, chrome_data_dir, chrome_launch_args, is_connection_closed,
+        protocol_dump_line, startup_backoff,
+    };
+    use std::path::Path;
 
     #[test]
     fn chrome_launch_args_include_debug_port_and_profile() {
-        let args = chrome_launch_args(9222);
+        let args = chrome_launch_args(
+            9222,
+            Path::new("/tmp/browser-cli-chrome-9222"),
+            false,
+            false,
+        );
 
         assert!(args.contains(&"--remote-debugging-port=9222".to_string()));
         assert!(args.contains(&"--user-data-dir=/tmp/browser-cli-chrome-9222".to_string()));
         assert!(args.contains(&"--no-first-run".to_string()));
         assert!(args.contains(&"--no-default-browser-check".to_string()));
         assert_eq!(args.last().map(String::as_str), Some("about:blank"));
+        assert!(!args.contains(&"--no-sandbox".to_string()));
+        assert!(!args.contains(&"--ignore-certificate-errors".to_string()));
+    }
+
+    #[test]
+    fn protocol_dump_line_keeps_raw_result_or_error() {
+        let params = serde_json::json!({ "expression": "1" });
+        let ok = serde_json::json!({ "id": 3, "result": { "result": { "type": "number", "value": 1 } } });
+        let line = protocol_dump_line("Runtime.evaluate", &params, &ok);
+
+        assert_eq!(line["method"], "Runtime.evaluate");
+        assert_eq!(line["params"], params);
+        assert_eq!(line["result"]["result"]["value"], 1);
+        assert!(line.get("error").is_none());
+
+        let failed = serde_json::json!({ "id": 4, "error": { "code": -32601, "message": "nope" } });
+        let line = protocol_dump_line("Page.bogus", &serde_json::json!({}), &failed);
+        assert_eq!(line["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn chrome_data_dir_defaults_per_port_and_rejects_path_like_profiles() {
+        assert_eq!(
+            chrome_data_dir(9333, None).unwrap(),
+            Path::new("/tmp/browser-cli-chrome-9333")
+        );
+        assert!(
+            chrome_data_dir(9222, Some("work"))
+                .is_ok_and(|dir| { dir.ends_with("browser-cli/profiles/work") })
+        );
+        assert!(chrome_data_dir(9222, Some("../work")).is_err());
+        assert!(chrome_data_dir(9222, Some("")).is_err());
+    }
+
+    #[test]
+    fn chrome_launch_args_disable_sandbox_when_requested() {
+        let args = chrome_launch_args(9222, Path::new("/tmp/browser-cli-chrome-9222"), true, true);
+
+        assert!(args.contains(&"--no-sandbox".to_string()));
+        assert!(args.contains(&"--ignore-certificate-errors".to_string()));
+        assert!(args.contains(&"--disable-dev-shm-usage".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("about:blank"));
+    }
+
+    #[test]
+    fn connection_closed_is_detected_but_cdp_errors_are_not() {
+        let closed: anyhow::Error = ConnectionClosed.into();
+        let cdp_error = anyhow::anyhow!("CDP error: {{}}");
+
+        assert!(is_connection_closed(&closed));
+        assert!(!is_connection_closed(&cdp_error));
+    }
+
+    #[test]
+    fn startup_backoff_doubles_and_caps() {
+        let delays: Vec<u64> = (0..8).map(startup_backoff).collect();
+
+        assert_eq!(delays, vec![50, 100, 200, 400, 800, 1000, 1000, 1000]);
     }
 }
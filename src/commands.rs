@@ -2,141 +2,717 @@ use anyhow::{Context, Result, anyhow};
 use std::path::PathBuf;
 
 use crate::cdp::{self, CdpConnection};
+use crate::output::print_action;
+use crate::selector;
 use crate::snapshot::{self, SnapshotOptions};
 
+const REF_SELECTOR_SCRIPT_TEMPLATE: &str = r#"(() => {
+    const el = __ELEMENT__;
+    if (!el) throw new Error('Element not found');
+    const unique = (sel) => document.querySelectorAll(sel).length === 1;
+    if (el.id && unique('#' + CSS.escape(el.id))) return '#' + CSS.escape(el.id);
+    const parts = [];
+    let node = el;
+    while (node && node.nodeType === 1 && node !== document.documentElement) {
+        if (node !== el && node.id && unique('#' + CSS.escape(node.id))) {
+            parts.unshift('#' + CSS.escape(node.id));
+            break;
+        }
+        const tag = node.tagName.toLowerCase();
+        const parent = node.parentElement;
+        const same = parent ? Array.from(parent.children).filter((c) => c.tagName === node.tagName) : [];
+        parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(node) + 1})` : tag);
+        node = parent;
+    }
+    return parts.join(' > ');
+})()"#;
+
 const WAIT_SELECTOR_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
     const check = () => {
-        if (document.querySelector(__SELECTOR__)) resolve(true);
+        if (__ELEMENT__) resolve(true);
+        else setTimeout(check, 100);
+    };
+    setTimeout(() => reject(new Error('Timeout: element did not appear')), 30000);
+    check();
+})"#;
+
+const WAIT_FUNCTION_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
+    const deadline = Date.now() + 30000;
+    const check = () => {
+        let ready;
+        try {
+            ready = !!(__EXPRESSION__);
+        } catch (e) {
+            reject(e);
+            return;
+        }
+        if (ready) resolve(true);
+        else if (Date.now() > deadline) reject(new Error('Timeout: condition stayed false'));
+        else setTimeout(check, 100);
+    };
+    check();
+})"#;
+
+const WAIT_LOAD_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
+    const check = () => {
+        if (__CONDITION__) resolve(true);
+        else setTimeout(check, 100);
+    };
+    setTimeout(() => reject(new Error('Timeout: page did not finish loading')), 30000);
+    check();
+})"#;
+
+const VALUE_SCRIPT_TEMPLATE: &str = r#"(() => {
+    const el = __ELEMENT__;
+    if (!el) return { kind: 'missing', value: '' };
+    if (el instanceof HTMLInputElement && (el.type === 'checkbox' || el.type === 'radio')) {
+        return { kind: el.type, value: el.checked };
+    }
+    if (el instanceof HTMLSelectElement && el.multiple) {
+        return { kind: 'select-multiple', value: Array.from(el.selectedOptions).map((o) => o.value) };
+    }
+    return { kind: 'value', value: el.value ?? '' };
+})()"#;
+
+/// Resolves once neither DOM mutations nor finished resource loads have been
+/// seen for `__QUIET_MS__` milliseconds.
+const WAIT_STABLE_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
+    const quietMs = __QUIET_MS__;
+    const started = performance.now();
+    let last = started;
+    let resources = performance.getEntriesByType('resource').length;
+    const observer = new MutationObserver(() => { last = performance.now(); });
+    observer.observe(document, { subtree: true, childList: true, attributes: true, characterData: true });
+    const deadline = Date.now() + 30000;
+    const check = () => {
+        const count = performance.getEntriesByType('resource').length;
+        if (count !== resources) {
+            resources = count;
+            last = performance.now();
+        }
+        const now = performance.now();
+        if (now - last >= quietMs) {
+            observer.disconnect();
+            resolve(Math.round(now - started));
+        } else if (Date.now() > deadline) {
+            observer.disconnect();
+            reject(new Error('Timeout: page kept changing'));
+        } else {
+            setTimeout(check, 50);
+        }
+    };
+    check();
+})"#;
+
+const WAIT_ANY_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
+    const conditions = [__CONDITIONS__];
+    const deadline = Date.now() + __TIMEOUT__;
+    const check = () => {
+        for (let i = 0; i < conditions.length; i++) {
+            let hit = false;
+            try {
+                hit = !!conditions[i]();
+            } catch (e) {
+                // An invalid selector throws on every tick, so report it now
+                // instead of polling until the timeout.
+                if (e instanceof SyntaxError || e instanceof DOMException) {
+                    reject(e);
+                    return;
+                }
+            }
+            if (hit) {
+                resolve(i);
+                return;
+            }
+        }
+        if (Date.now() > deadline) reject(new Error('Timeout: none of the conditions matched'));
         else setTimeout(check, 100);
     };
-    setTimeout(() => reject('Timeout'), 30000);
     check();
 })"#;
 
-pub async fn cmd_open(port: u16, url: String, json: bool) -> Result<()> {
+/// Options for `open`
+pub struct OpenOptions {
+    pub wait_for: Option<String>,
+    pub wait_load: Option<String>,
+    pub user_agent: Option<String>,
+    pub accept_language: Option<String>,
+    /// Open in a fresh tab instead of replacing the active page
+    pub new_tab: bool,
+    /// Load the page with its Content-Security-Policy disabled
+    pub bypass_csp: bool,
+}
+
+/// User agent presets as (name, user agent, platform)
+const USER_AGENT_PRESETS: &[(&str, &str, &str)] = &[
+    (
+        "googlebot",
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+        "",
+    ),
+    (
+        "iphone",
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+        "iPhone",
+    ),
+    (
+        "android",
+        "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        "Linux armv8l",
+    ),
+];
+
+/// Resolve a preset name to (user agent, platform); anything else is used verbatim.
+pub(crate) fn resolve_user_agent(value: &str) -> (&str, Option<&str>) {
+    USER_AGENT_PRESETS
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, ua, platform)| (*ua, Some(*platform).filter(|p| !p.is_empty())))
+        .unwrap_or((value, None))
+}
+
+async fn apply_user_agent(
+    cdp: &mut CdpConnection,
+    user_agent: &str,
+    accept_language: Option<&str>,
+) -> Result<()> {
+    let (user_agent, platform) = resolve_user_agent(user_agent);
+    let mut params = serde_json::json!({ "userAgent": user_agent });
+    if let Some(language) = accept_language {
+        params["acceptLanguage"] = serde_json::json!(language);
+    }
+    if let Some(platform) = platform {
+        params["platform"] = serde_json::json!(platform);
+    }
+    cdp.send("Network.setUserAgentOverride", params.clone())
+        .await?;
+    if platform.is_some() || accept_language.is_some() {
+        cdp.send("Emulation.setUserAgentOverride", params).await?;
+    }
+    Ok(())
+}
+
+pub async fn cmd_open(port: u16, url: String, opts: &OpenOptions, json: bool) -> Result<()> {
     let url = if url.contains("://") {
         url
     } else {
         format!("https://{}", url)
     };
     let targets = cdp::get_targets(port).await?;
-    let any_target = targets.first().context("No browser targets")?;
-    let ws_url = any_target.webSocketDebuggerUrl.as_ref().unwrap();
-    let mut cdp = CdpConnection::connect(ws_url).await?;
+    let (ws_url, new_tab_id) = if opts.new_tab {
+        let target = cdp::create_new_tab(port, "about:blank").await?;
+        let ws_url = target
+            .webSocketDebuggerUrl
+            .context("New tab has no debugger URL")?;
+        (ws_url, Some(target.id))
+    } else {
+        let target = match cdp::pinned_target(&targets)? {
+            Some(target) => target,
+            None => targets.first().context("No browser targets")?,
+        };
+        (target.webSocketDebuggerUrl.clone().unwrap(), None)
+    };
+    let mut cdp = CdpConnection::connect(&ws_url).await?;
+
+    // A new tab has nothing else to look at, so wait for it to finish loading.
+    let wait_load = match (opts.wait_load.as_deref(), opts.new_tab) {
+        (Some(state), _) => Some(state),
+        (None, true) => Some("load"),
+        (None, false) => None,
+    };
+    // Network idle has to be watched from before the navigation starts.
+    let mut idle_watcher = match wait_load.and_then(crate::network::network_idle_threshold) {
+        Some(max_inflight) => {
+            let watcher = CdpConnection::connect(&ws_url).await?;
+            Some((
+                crate::network::NetworkIdleWatcher::start(watcher).await?,
+                max_inflight,
+            ))
+        }
+        None => None,
+    };
 
+    if let Some(user_agent) = opts.user_agent.as_deref() {
+        apply_user_agent(&mut cdp, user_agent, opts.accept_language.as_deref()).await?;
+    }
+    if opts.bypass_csp {
+        cdp.bypass_csp().await?;
+    }
     cdp.send("Page.navigate", serde_json::json!({ "url": url }))
         .await?;
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    if let Some((watcher, max_inflight)) = idle_watcher.as_mut() {
+        watcher.wait(*max_inflight).await?;
+    } else if let Some(state) = wait_load {
+        wait_for_load_state(&mut cdp, state).await?;
+    }
+    if let Some(selector) = opts.wait_for.as_deref() {
+        wait_for_selector(&mut cdp, selector).await?;
+    }
 
     let title = cdp.eval("document.title").await?;
     let final_url = cdp.eval("window.location.href").await?;
+    let tab_index = match new_tab_id {
+        Some(id) => cdp::get_targets(port)
+            .await?
+            .iter()
+            .position(|t| t.id == id),
+        None => None,
+    };
+
+    if json {
+        let mut result = serde_json::json!({ "title": title, "url": final_url });
+        if opts.new_tab {
+            result["tab"] = serde_json::json!(tab_index);
+        }
+        println!("{}", result);
+    } else {
+        println!("✓ {}", title.as_str().unwrap_or(""));
+        println!("  {}", final_url.as_str().unwrap_or(""));
+        if let Some(idx) = tab_index {
+            println!("  opened in tab {}", idx);
+        }
+    }
+    Ok(())
+}
 
+pub async fn cmd_info(port: u16, json: bool) -> Result<()> {
+    let version = cdp::get_version(port).await?;
+    let field = |key: &str| version.get(key).and_then(|v| v.as_str()).unwrap_or("");
     if json {
         println!(
             "{}",
-            serde_json::json!({ "title": title, "url": final_url })
+            serde_json::json!({
+                "browser": field("Browser"),
+                "protocolVersion": field("Protocol-Version"),
+                "v8Version": field("V8-Version"),
+                "webkitVersion": field("WebKit-Version"),
+                "userAgent": field("User-Agent"),
+                "port": port,
+            })
         );
     } else {
-        println!("✓ {}", title.as_str().unwrap_or(""));
-        println!("  {}", final_url.as_str().unwrap_or(""));
+        println!("Browser:  {}", field("Browser"));
+        println!("Protocol: {}", field("Protocol-Version"));
+        println!("V8:       {}", field("V8-Version"));
+        println!("WebKit:   {}", field("WebKit-Version"));
+        println!("UA:       {}", field("User-Agent"));
     }
     Ok(())
 }
 
-pub async fn cmd_simple_page(port: u16, method: &str, label: &str) -> Result<()> {
+pub async fn cmd_simple_page(port: u16, method: &str, label: &str, json: bool) -> Result<()> {
     let mut cdp = cdp::connect_active(port).await?;
     cdp.send(method, serde_json::json!({})).await?;
-    println!("✓ {}", label);
+    print_action(
+        json,
+        method.trim_start_matches("Page."),
+        serde_json::json!({}),
+        label,
+    );
+    Ok(())
+}
+
+/// Terminate the browser. Chrome usually drops the socket before answering,
+/// which counts as success here.
+pub async fn cmd_quit(port: u16, json: bool) -> Result<()> {
+    // Don't launch Chrome just to shut it down again.
+    if !cdp::chrome_is_running(port).await {
+        print_action(
+            json,
+            "quit",
+            serde_json::json!({ "running": false }),
+            "Browser is not running",
+        );
+        return Ok(());
+    }
+    let mut cdp = cdp::connect_browser(port).await?;
+    match cdp.send("Browser.close", serde_json::json!({})).await {
+        Err(err) if !cdp::is_connection_closed(&err) => return Err(err),
+        _ => {}
+    }
+    print_action(json, "quit", serde_json::json!({}), "Browser closed");
+    Ok(())
+}
+
+pub async fn cmd_history(
+    port: u16,
+    action: Option<&crate::HistoryCommand>,
+    json: bool,
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let history = cdp
+        .send("Page.getNavigationHistory", serde_json::json!({}))
+        .await?;
+    let current = history
+        .get("currentIndex")
+        .and_then(|i| i.as_u64())
+        .unwrap_or(0) as usize;
+    let entries = history
+        .get("entries")
+        .and_then(|e| e.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    match action {
+        None | Some(crate::HistoryCommand::List) => print_history(&entries, current, json),
+        Some(crate::HistoryCommand::Go { index }) => {
+            let entry = entries.get(*index).context("History index out of range")?;
+            let entry_id = entry
+                .get("id")
+                .cloned()
+                .context("History entry has no id")?;
+            cdp.send(
+                "Page.navigateToHistoryEntry",
+                serde_json::json!({ "entryId": entry_id }),
+            )
+            .await?;
+            let url = entry.get("url").and_then(|u| u.as_str()).unwrap_or("");
+            print_action(
+                json,
+                "history.go",
+                serde_json::json!({ "index": index, "url": url }),
+                &format!("Went to history entry {}: {}", index, url),
+            );
+            Ok(())
+        }
+    }
+}
+
+fn print_history(entries: &[serde_json::Value], current: usize, json: bool) -> Result<()> {
+    let field = |entry: &serde_json::Value, key: &str| {
+        entry
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    if json {
+        let entries: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                serde_json::json!({
+                    "index": i,
+                    "title": field(entry, "title"),
+                    "url": field(entry, "url"),
+                    "current": i == current,
+                })
+            })
+            .collect();
+        return crate::output::print_list(&entries);
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let marker = if i == current { "*" } else { " " };
+        println!(
+            "{} {}: {} - {}",
+            marker,
+            i,
+            field(entry, "title"),
+            field(entry, "url")
+        );
+    }
+    Ok(())
+}
+
+/// JS expression for the element matching `selector`: the first match, or the
+/// `nth` (0-based) match, throwing when `nth` is out of range.
+pub(crate) fn element_expr(selector: &str, nth: Option<usize>) -> Result<String> {
+    Ok(match nth {
+        None => selector::first_expr(selector)?,
+        Some(i) => format!(
+            "((all) => {{ if (all.length <= {i}) throw new Error(`No element at index {i} (${{all.length}} matches)`); return all[{i}]; }})({})",
+            selector::all_expr(selector)?
+        ),
+    })
+}
+
+/// Resolves `true` once __PRESENT__ holds, or `false` after __TIMEOUT__ ms.
+const AWAIT_ELEMENT_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve) => {
+    const deadline = Date.now() + __TIMEOUT__;
+    const check = () => {
+        if (__PRESENT__) resolve(true);
+        else if (Date.now() > deadline) resolve(false);
+        else setTimeout(check, 50);
+    };
+    check();
+})"#;
+
+/// Give a missing element up to `--select-timeout` to appear, on the connection
+/// the command then acts on. Never fails on its own account: if the element is
+/// still missing the command reports it as usual.
+pub(crate) async fn await_element(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> Result<()> {
+    let timeout_ms = selector::select_timeout();
+    if timeout_ms == 0 {
+        return Ok(());
+    }
+    let present = match nth {
+        None => format!("({}) != null", selector::first_expr(selector)?),
+        Some(i) => format!("{}.length > {}", selector::all_expr(selector)?, i),
+    };
+    let script = AWAIT_ELEMENT_SCRIPT_TEMPLATE
+        .replace("__TIMEOUT__", &timeout_ms.to_string())
+        .replace("__PRESENT__", &present);
+    eval_promise(cdp, &script).await?;
     Ok(())
 }
 
-pub async fn cmd_click(port: u16, selector: &str) -> Result<()> {
+pub async fn cmd_click(
+    port: u16,
+    selector: &str,
+    nth: Option<usize>,
+    all: bool,
+    allow_empty: bool,
+    json: bool,
+) -> Result<()> {
     let mut cdp = cdp::connect_active(port).await?;
+    if all {
+        return click_all(&mut cdp, selector, allow_empty, json).await;
+    }
+    click_element(&mut cdp, selector, nth).await?;
+    print_action(
+        json,
+        "click",
+        serde_json::json!({ "selector": selector }),
+        "Clicked",
+    );
+    Ok(())
+}
+
+pub(crate) async fn click_element(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> Result<()> {
+    await_element(cdp, selector, nth).await?;
     let script = format!(
         r#"(() => {{
-            const el = document.querySelector({});
+            const el = {};
             if (!el) throw new Error('Element not found');
             el.click();
             return true;
         }})()"#,
-        serde_json::to_string(selector)?
+        element_expr(selector, nth)?
     );
     cdp.eval(&script).await?;
-    println!("✓ Clicked");
     Ok(())
 }
 
-async fn set_input_value(port: u16, selector: &str, text: &str, append: bool) -> Result<()> {
-    let mut cdp = cdp::connect_active(port).await?;
-    if !append && is_file_input(&mut cdp, selector).await? {
-        set_file_input_files(&mut cdp, selector, &[text.to_string()]).await?;
-        return Ok(());
+async fn click_all(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    allow_empty: bool,
+    json: bool,
+) -> Result<()> {
+    // With --allow-empty no match is a valid outcome, so don't wait for one.
+    if !allow_empty {
+        await_element(cdp, selector, None).await?;
     }
-
-    let op = if append { "+=" } else { "=" };
     let script = format!(
         r#"(() => {{
-            const el = document.querySelector({});
-            if (!el) throw new Error('Element not found');
-            el.focus();
-            el.value {} {};
-            el.dispatchEvent(new Event('input', {{ bubbles: true }}));
-            return true;
+            const els = {};
+            els.forEach((el) => el.click());
+            return els.length;
         }})()"#,
-        serde_json::to_string(selector)?,
-        op,
-        serde_json::to_string(text)?
+        selector::all_expr(selector)?
+    );
+    let count = cdp.eval(&script).await?.as_u64().unwrap_or(0);
+    if count == 0 && !allow_empty {
+        return Err(anyhow!("No elements matched {}", selector));
+    }
+    print_action(
+        json,
+        "click",
+        serde_json::json!({ "selector": selector, "count": count }),
+        &format!(
+            "Clicked {} element{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        ),
     );
+    Ok(())
+}
+
+async fn set_input_value(
+    port: u16,
+    selector: &str,
+    nth: Option<usize>,
+    text: &str,
+    append: bool,
+    clear_first: bool,
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    await_element(&mut cdp, selector, nth).await?;
+    if !append && is_file_input(&mut cdp, selector, nth).await? {
+        set_file_input_files(&mut cdp, selector, nth, &[text.to_string()]).await?;
+        return Ok(());
+    }
+
+    let script = build_input_value_script(selector, nth, text, append, clear_first)?;
     cdp.eval(&script).await?;
     Ok(())
 }
 
-pub async fn cmd_type(port: u16, selector: &str, text: &str) -> Result<()> {
-    set_input_value(port, selector, text, true).await?;
-    println!("✓ Typed");
+/// Set or append to a field's value. With `__CLEAR__`, the field is first emptied
+/// through the native `value` setter, so frameworks that track the value (React
+/// and friends) see the change, and `input` and `change` fire for it.
+const INPUT_VALUE_SCRIPT_TEMPLATE: &str = r#"(() => {
+    const el = __ELEMENT__;
+    if (!el) throw new Error('Element not found');
+    el.focus();
+    if (__CLEAR__) {
+        const proto = el instanceof HTMLTextAreaElement ? HTMLTextAreaElement.prototype
+            : el instanceof HTMLSelectElement ? HTMLSelectElement.prototype
+            : HTMLInputElement.prototype;
+        const setter = Object.getOwnPropertyDescriptor(proto, 'value')?.set;
+        if (setter) setter.call(el, '');
+        else el.value = '';
+        el.dispatchEvent(new Event('input', { bubbles: true }));
+        el.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    el.value __OP__ __TEXT__;
+    el.dispatchEvent(new Event('input', { bubbles: true }));
+    return true;
+})()"#;
+
+fn build_input_value_script(
+    selector: &str,
+    nth: Option<usize>,
+    text: &str,
+    append: bool,
+    clear_first: bool,
+) -> Result<String> {
+    Ok(INPUT_VALUE_SCRIPT_TEMPLATE
+        .replace("__ELEMENT__", &element_expr(selector, nth)?)
+        .replace("__CLEAR__", &clear_first.to_string())
+        .replace("__OP__", if append { "+=" } else { "=" })
+        .replace("__TEXT__", &serde_json::to_string(text)?))
+}
+
+pub async fn cmd_type(
+    port: u16,
+    selector: &str,
+    nth: Option<usize>,
+    text: &str,
+    clear_first: bool,
+    json: bool,
+) -> Result<()> {
+    set_input_value(port, selector, nth, text, true, clear_first).await?;
+    print_action(
+        json,
+        "type",
+        serde_json::json!({ "selector": selector, "clearFirst": clear_first }),
+        "Typed",
+    );
     Ok(())
 }
 
-pub async fn cmd_fill(port: u16, selector: &str, text: &str) -> Result<()> {
-    set_input_value(port, selector, text, false).await?;
-    println!("✓ Filled");
+pub async fn cmd_fill(
+    port: u16,
+    selector: &str,
+    nth: Option<usize>,
+    text: &str,
+    json: bool,
+) -> Result<()> {
+    set_input_value(port, selector, nth, text, false, false).await?;
+    print_action(
+        json,
+        "fill",
+        serde_json::json!({ "selector": selector }),
+        "Filled",
+    );
     Ok(())
 }
 
-pub async fn cmd_attach(port: u16, selector: &str, files: &[String]) -> Result<()> {
+pub async fn cmd_attach(port: u16, selector: &str, files: &[String], json: bool) -> Result<()> {
     if files.is_empty() {
         return Err(anyhow!("At least one file path is required"));
     }
 
     let mut cdp = cdp::connect_active(port).await?;
-    if !is_file_input(&mut cdp, selector).await? {
+    await_element(&mut cdp, selector, None).await?;
+    if !is_file_input(&mut cdp, selector, None).await? {
         return Err(anyhow!("Element is not an input[type=file]"));
     }
 
-    set_file_input_files(&mut cdp, selector, files).await?;
-    println!(
-        "✓ Attached {} file{}",
-        files.len(),
-        if files.len() == 1 { "" } else { "s" }
+    set_file_input_files(&mut cdp, selector, None, files).await?;
+    print_action(
+        json,
+        "attach",
+        serde_json::json!({ "selector": selector, "files": files }),
+        &format!(
+            "Attached {} file{}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" }
+        ),
     );
     Ok(())
 }
 
-async fn is_file_input(cdp: &mut CdpConnection, selector: &str) -> Result<bool> {
+async fn is_file_input(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> Result<bool> {
     let script = format!(
         r#"(() => {{
-            const el = document.querySelector({});
+            const el = {};
             if (!el) throw new Error('Element not found');
             return el instanceof HTMLInputElement && el.type === 'file';
         }})()"#,
-        serde_json::to_string(selector)?
+        element_expr(selector, nth)?
     );
     Ok(cdp.eval(&script).await?.as_bool().unwrap_or(false))
 }
 
+/// Remote object id of the first (or `nth`) element matching `selector`.
+pub(crate) async fn element_object_id(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> Result<String> {
+    let result = cdp
+        .send(
+            "Runtime.evaluate",
+            serde_json::json!({ "expression": element_expr(selector, nth)? }),
+        )
+        .await?;
+    if let Some(details) = result.get("exceptionDetails") {
+        return Err(anyhow!("{}", cdp::exception_message(details)));
+    }
+    result
+        .pointer("/result/objectId")
+        .and_then(|id| id.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("Element not found: {}", selector))
+}
+
+/// Resolve the DOM `nodeId` of the first (or `nth`) element matching `selector`.
+async fn query_node_id(cdp: &mut CdpConnection, selector: &str, nth: Option<usize>) -> Result<i64> {
+    // The DOM agent only hands out node ids once the document has been requested.
+    cdp.send("DOM.getDocument", serde_json::json!({ "depth": 0 }))
+        .await?;
+    let object_id = element_object_id(cdp, selector, nth).await?;
+    let node = cdp
+        .send(
+            "DOM.requestNode",
+            serde_json::json!({ "objectId": object_id }),
+        )
+        .await?;
+    node.get("nodeId")
+        .and_then(|id| id.as_i64())
+        .filter(|id| *id != 0)
+        .context("Element not found")
+}
+
 async fn set_file_input_files(
     cdp: &mut CdpConnection,
     selector: &str,
+    nth: Option<usize>,
     files: &[String],
 ) -> Result<()> {
     let files = files
@@ -152,28 +728,7 @@ async fn set_file_input_files(
         })
         .collect::<Result<Vec<_>>>()?;
 
-    let document = cdp
-        .send("DOM.getDocument", serde_json::json!({ "depth": 0 }))
-        .await?;
-    let root_node_id = document
-        .get("root")
-        .and_then(|root| root.get("nodeId"))
-        .and_then(|node_id| node_id.as_i64())
-        .context("DOM.getDocument did not return a root node")?;
-    let node = cdp
-        .send(
-            "DOM.querySelector",
-            serde_json::json!({
-                "nodeId": root_node_id,
-                "selector": selector,
-            }),
-        )
-        .await?;
-    let node_id = node
-        .get("nodeId")
-        .and_then(|node_id| node_id.as_i64())
-        .filter(|node_id| *node_id != 0)
-        .context("Element not found")?;
+    let node_id = query_node_id(cdp, selector, nth).await?;
 
     cdp.send(
         "DOM.setFileInputFiles",
@@ -186,41 +741,62 @@ async fn set_file_input_files(
 
     let event_script = format!(
         r#"(() => {{
-            const el = document.querySelector({});
+            const el = {};
             if (!el) throw new Error('Element not found');
             el.dispatchEvent(new Event('input', {{ bubbles: true }}));
             el.dispatchEvent(new Event('change', {{ bubbles: true }}));
             return Array.from(el.files || []).map((file) => file.name);
         }})()"#,
-        serde_json::to_string(selector)?
+        element_expr(selector, nth)?
     );
     cdp.eval(&event_script).await?;
     Ok(())
 }
 
-pub async fn cmd_press(port: u16, key: &str) -> Result<()> {
+pub async fn cmd_press(port: u16, key: &str, json: bool) -> Result<()> {
     let mut cdp = cdp::connect_active(port).await?;
-    cdp.send(
-        "Input.dispatchKeyEvent",
-        serde_json::json!({ "type": "keyDown", "key": key }),
-    )
-    .await?;
-    cdp.send(
-        "Input.dispatchKeyEvent",
-        serde_json::json!({ "type": "keyUp", "key": key }),
-    )
-    .await?;
-    println!("✓ Pressed {}", key);
+    crate::input::press_key(&mut cdp, key).await?;
+    print_action(
+        json,
+        "press",
+        serde_json::json!({ "key": key }),
+        &format!("Pressed {}", key),
+    );
     Ok(())
 }
 
-pub async fn cmd_screenshot(port: u16, path: &str, full: bool) -> Result<()> {
+pub async fn cmd_screenshot(
+    port: u16,
+    path: &std::path::Path,
+    full: bool,
+    dsf: Option<f64>,
+    diff: Option<&crate::screenshot_diff::DiffOptions>,
+    json: bool,
+) -> Result<()> {
+    if let Some(dsf) = dsf
+        && !(1.0..=4.0).contains(&dsf)
+    {
+        return Err(anyhow!("--dsf must be between 1 and 4"));
+    }
     let mut cdp = cdp::connect_active(port).await?;
-    let mut params = serde_json::json!({ "format": "jpeg", "quality": 15 });
+    // Compare lossless captures: JPEG artifacts would show up as changed pixels.
+    // Compared captures are saved as PNG so they can serve as the next baseline.
+    let path = match diff {
+        Some(_) => path.with_extension("png"),
+        None => path.to_path_buf(),
+    };
+    let mut params = if crate::screenshot_diff::is_png(&path) {
+        serde_json::json!({ "format": "png" })
+    } else {
+        serde_json::json!({ "format": "jpeg", "quality": 15 })
+    };
     if full {
         params["captureBeyondViewport"] = serde_json::json!(true);
     }
-    let result = cdp.send("Page.captureScreenshot", params).await?;
+    let result = match dsf {
+        Some(dsf) => capture_with_scale_factor(&mut cdp, params, dsf).await?,
+        None => cdp.send("Page.captureScreenshot", params).await?,
+    };
     let data = result
         .get("data")
         .and_then(|d| d.as_str())
@@ -228,115 +804,866 @@ pub async fn cmd_screenshot(port: u16, path: &str, full: bool) -> Result<()> {
 
     use base64::Engine;
     let bytes = base64::engine::general_purpose::STANDARD.decode(data)?;
-    std::fs::write(path, bytes)?;
-    println!("✓ Screenshot saved to {}", path);
-    Ok(())
-}
+    std::fs::write(&path, &bytes)?;
 
-pub async fn cmd_eval(port: u16, script: &str, json: bool) -> Result<()> {
-    let mut cdp = cdp::connect_active(port).await?;
-    let result = cdp.eval(script).await?;
-    if json {
-        println!("{}", serde_json::to_string(&result)?);
-    } else {
-        println!("{}", serde_json::to_string_pretty(&result)?);
+    let Some(diff) = diff else {
+        print_action(
+            json,
+            "screenshot",
+            serde_json::json!({ "path": path }),
+            &format!("Screenshot saved to {}", path.display()),
+        );
+        return Ok(());
+    };
+    let report = crate::screenshot_diff::compare(&bytes, diff)?;
+    let ratio = report.ratio();
+    if ratio > diff.threshold {
+        return Err(anyhow!(
+            "Screenshot differs from baseline: {} of {} pixels changed ({:.4} > threshold {})",
+            report.changed,
+            report.total,
+            ratio,
+            diff.threshold
+        ));
     }
+    print_action(
+        json,
+        "screenshot",
+        serde_json::json!({
+            "path": path,
+            "baseline": diff.baseline,
+            "diff": diff.diff_out,
+            "changedPixels": report.changed,
+            "ratio": ratio,
+        }),
+        &format!(
+            "Screenshot matches baseline ({} pixels changed, ratio {:.4})",
+            report.changed, ratio
+        ),
+    );
     Ok(())
 }
 
-pub async fn cmd_snapshot(
+/// Page-relative bounding rect of every match, so clips work without scrolling.
+const ELEMENT_RECTS_SCRIPT_TEMPLATE: &str = r#"__ELEMENTS__.map(el => {
+    const r = el.getBoundingClientRect();
+    return { x: r.left + scrollX, y: r.top + scrollY, width: r.width, height: r.height };
+})"#;
+
+/// Screenshot each element matching `selector` to `path` with `-N` inserted
+/// before the extension. Zero-size matches are skipped but keep their number.
+pub async fn cmd_screenshot_elements(
     port: u16,
-    interactive: bool,
-    compact: bool,
-    react: bool,
-    depth: Option<usize>,
-    filter: Option<String>,
-    full: bool,
-    mini: bool,
+    path: &std::path::Path,
+    selector: &str,
+    dsf: Option<f64>,
+    json: bool,
 ) -> Result<()> {
+    if let Some(dsf) = dsf
+        && !(1.0..=4.0).contains(&dsf)
+    {
+        return Err(anyhow!("--dsf must be between 1 and 4"));
+    }
     let mut cdp = cdp::connect_active(port).await?;
-    let opts = SnapshotOptions {
-        interactive,
-        compact,
-        react,
-        max_depth: depth,
-        filter,
-        full,
-        mini,
-    };
-    let output = snapshot::take_snapshot(&mut cdp, &opts).await?;
-    println!("{}", output);
-    Ok(())
-}
-
-pub async fn cmd_get(port: u16, what: &crate::GetCommand, json: bool) -> Result<()> {
-    let targets = cdp::get_targets(port).await?;
-    let target = cdp::find_active_target(&targets)?;
-    let ws = target.webSocketDebuggerUrl.as_ref().unwrap();
+    let script =
+        ELEMENT_RECTS_SCRIPT_TEMPLATE.replace("__ELEMENTS__", &selector::all_expr(selector)?);
+    let rects = cdp.eval(&script).await?;
+    let rects = rects.as_array().cloned().unwrap_or_default();
+    if rects.is_empty() {
+        return Err(anyhow!("Element not found: {}", selector));
+    }
 
-    match what {
-        crate::GetCommand::Title => print_field(json, "title", &target.title),
-        crate::GetCommand::Url => print_field(json, "url", &target.url),
-        crate::GetCommand::Text { selector } => {
-            eval_and_print_str(ws, &build_text_script(selector)?).await?;
-        }
-        crate::GetCommand::Html { selector } => {
-            eval_selector_field(ws, selector, "innerHTML").await?;
-        }
-        crate::GetCommand::Value { selector } => {
-            eval_selector_field(ws, selector, "value").await?;
-        }
-        crate::GetCommand::Attr { selector, name } => {
-            eval_selector_attr(ws, selector, name).await?;
-        }
-        crate::GetCommand::Count { selector } => {
-            eval_selector_count(ws, selector).await?;
+    use base64::Engine;
+    let mut saved = Vec::new();
+    for (i, rect) in rects.iter().enumerate() {
+        let dimension = |key: &str| rect.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        if dimension("width") <= 0.0 || dimension("height") <= 0.0 {
+            eprintln!(
+                "warning: skipping match {} of {}: element has no size",
+                i, selector
+            );
+            continue;
         }
+        let params = serde_json::json!({
+            "format": "jpeg",
+            "quality": 15,
+            "captureBeyondViewport": true,
+            "clip": {
+                "x": dimension("x"),
+                "y": dimension("y"),
+                "width": dimension("width"),
+                "height": dimension("height"),
+                "scale": 1,
+            },
+        });
+        let result = match dsf {
+            Some(dsf) => capture_with_scale_factor(&mut cdp, params, dsf).await?,
+            None => cdp.send("Page.captureScreenshot", params).await?,
+        };
+        let data = result
+            .get("data")
+            .and_then(|d| d.as_str())
+            .context("No screenshot data")?;
+        let file = numbered_path(path, i);
+        std::fs::write(
+            &file,
+            base64::engine::general_purpose::STANDARD.decode(data)?,
+        )?;
+        saved.push(file);
     }
-    Ok(())
-}
 
-async fn eval_selector_field(ws_url: &str, selector: &str, field: &str) -> Result<()> {
-    let script = format!(
-        "document.querySelector({})?.{} || ''",
-        serde_json::to_string(selector)?,
-        field
+    print_action(
+        json,
+        "screenshot",
+        serde_json::json!({ "selector": selector, "count": saved.len(), "paths": saved }),
+        &format!(
+            "Saved {} element screenshot(s): {}",
+            saved.len(),
+            saved
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
     );
-    eval_and_print_str(ws_url, &script).await
+    Ok(())
 }
 
-async fn eval_selector_attr(ws_url: &str, selector: &str, name: &str) -> Result<()> {
-    let script = format!(
-        "document.querySelector({})?.getAttribute({}) || ''",
-        serde_json::to_string(selector)?,
-        serde_json::to_string(name)?
-    );
-    eval_and_print_str(ws_url, &script).await
+/// `shot.jpg` -> `shot-3.jpg`
+pub(crate) fn numbered_path(path: &std::path::Path, index: usize) -> std::path::PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, index),
+    };
+    path.with_file_name(name)
 }
 
-async fn eval_selector_count(ws_url: &str, selector: &str) -> Result<()> {
-    let script = format!(
-        "document.querySelectorAll({}).length",
-        serde_json::to_string(selector)?
-    );
-    let result = CdpConnection::connect(ws_url).await?.eval(&script).await?;
-    println!("{}", result);
-    Ok(())
+/// Capture with a temporary device scale factor, keeping the current viewport size.
+async fn capture_with_scale_factor(
+    cdp: &mut CdpConnection,
+    params: serde_json::Value,
+    dsf: f64,
+) -> Result<serde_json::Value> {
+    let metrics = cdp
+        .send("Page.getLayoutMetrics", serde_json::json!({}))
+        .await?;
+    let viewport = metrics
+        .get("cssLayoutViewport")
+        .context("Page.getLayoutMetrics did not return a viewport")?;
+    let width = viewport
+        .get("clientWidth")
+        .and_then(|w| w.as_i64())
+        .unwrap_or(0);
+    let height = viewport
+        .get("clientHeight")
+        .and_then(|h| h.as_i64())
+        .unwrap_or(0);
+
+    cdp.send(
+        "Emulation.setDeviceMetricsOverride",
+        serde_json::json!({
+            "width": width,
+            "height": height,
+            "deviceScaleFactor": dsf,
+            "mobile": false,
+        }),
+    )
+    .await?;
+    let result = cdp.send("Page.captureScreenshot", params).await;
+    cdp.send(
+        "Emulation.clearDeviceMetricsOverride",
+        serde_json::json!({}),
+    )
+    .await?;
+    result
+}
+
+/// Archive the page as MHTML: the HTML plus stylesheets, images and frames in one file.
+pub async fn cmd_mhtml(port: u16, path: &std::path::Path, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let result = cdp
+        .send(
+            "Page.captureSnapshot",
+            serde_json::json!({ "format": "mhtml" }),
+        )
+        .await
+        .context("Page could not be captured as MHTML")?;
+    let data = result
+        .get("data")
+        .and_then(|d| d.as_str())
+        .context("Page.captureSnapshot returned no data")?;
+    std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display()))?;
+    print_action(
+        json,
+        "mhtml",
+        serde_json::json!({ "path": path, "bytes": data.len() }),
+        &format!("Saved MHTML to {} ({} bytes)", path.display(), data.len()),
+    );
+    Ok(())
+}
+
+/// Evaluate one script, or several in order on the same connection and context
+/// (so later scripts see earlier side effects), printing an array of results.
+pub async fn cmd_eval(
+    port: u16,
+    scripts: &[String],
+    json: bool,
+    raw: bool,
+    isolated: bool,
+    args: &[EvalArg],
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let context_id = if isolated {
+        Some(create_isolated_world(&mut cdp).await?)
+    } else {
+        None
+    };
+    let mut results = Vec::with_capacity(scripts.len());
+    let mut summarized = false;
+    for (i, script) in scripts.iter().enumerate() {
+        let evaluated = eval_for_display(&mut cdp, script, args, context_id).await;
+        let (result, preview) = if scripts.len() > 1 {
+            evaluated.with_context(|| format!("Script {} of {} failed", i + 1, scripts.len()))?
+        } else {
+            evaluated?
+        };
+        summarized |= preview;
+        results.push(result);
+    }
+    if summarized {
+        eprintln!("note: result could not be returned by value; showing an object preview");
+    }
+    if raw && results.len() > 1 {
+        // One line per script: strings unquoted, anything else as JSON
+        for result in &results {
+            match result.as_str() {
+                Some(text) => println!("{}", text),
+                None => println!("{}", result),
+            }
+        }
+        return Ok(());
+    }
+    let result = match results.len() {
+        1 => results.remove(0),
+        _ => serde_json::Value::Array(results),
+    };
+    if raw && let Some(text) = result.as_str() {
+        println!("{}", text);
+    } else if json {
+        println!("{}", serde_json::to_string(&result)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    }
+    Ok(())
+}
+
+/// A named `--arg` value for `eval`
+pub type EvalArg = (String, serde_json::Value);
+
+/// Parse `name=value`, where the value is JSON or, failing that, a plain string.
+pub fn parse_eval_arg(value: &str) -> Result<EvalArg, String> {
+    let (name, raw) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected name=value but got '{}'", value))?;
+    let valid_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !valid_name {
+        return Err(format!("'{}' is not a valid JavaScript identifier", name));
+    }
+    let parsed = serde_json::from_str(raw).unwrap_or_else(|_| serde_json::json!(raw));
+    Ok((name.to_string(), parsed))
 }
 
-fn build_text_script(selector: &Option<String>) -> Result<String> {
+/// With `--arg`, the script is the body of an async function taking the args by name.
+fn build_eval_function(script: &str, args: &[EvalArg]) -> String {
+    let names: Vec<&str> = args.iter().map(|(name, _)| name.as_str()).collect();
+    format!("async function({}) {{\n{}\n}}", names.join(", "), script)
+}
+
+/// Serialized results above this size are replaced by an object preview.
+const MAX_EVAL_RESULT_BYTES: usize = 1_000_000;
+
+/// Evaluate once without `returnByValue`, then try to serialize the result. Values
+/// that are circular, too deep or too large fall back to a rendered preview; the
+/// flag reports whether that happened.
+async fn eval_for_display(
+    cdp: &mut CdpConnection,
+    script: &str,
+    args: &[EvalArg],
+    context_id: Option<i64>,
+) -> Result<(serde_json::Value, bool)> {
+    let mut params = serde_json::json!({
+        "expression": if args.is_empty() { script } else { "globalThis" },
+        "returnByValue": false,
+        "generatePreview": true,
+        "objectGroup": "browser-cli-eval",
+    });
+    if let Some(id) = context_id {
+        params["contextId"] = serde_json::json!(id);
+    }
+    let mut result = cdp.send("Runtime.evaluate", params).await?;
+    if !args.is_empty() {
+        let global_id = result
+            .pointer("/result/objectId")
+            .and_then(|id| id.as_str())
+            .context("Could not resolve the global object")?;
+        let arguments: Vec<_> = args
+            .iter()
+            .map(|(_, value)| serde_json::json!({ "value": value }))
+            .collect();
+        result = cdp
+            .send(
+                "Runtime.callFunctionOn",
+                serde_json::json!({
+                    "objectId": global_id,
+                    "functionDeclaration": build_eval_function(script, args),
+                    "arguments": arguments,
+                    "returnByValue": false,
+                    "generatePreview": true,
+                    "awaitPromise": true,
+                    "objectGroup": "browser-cli-eval",
+                }),
+            )
+            .await?;
+    }
+    if let Some(details) = result.get("exceptionDetails") {
+        return Err(anyhow!("{}", cdp::exception_message(details)));
+    }
+    let object = result.get("result").cloned().unwrap_or_default();
+    let Some(object_id) = object.get("objectId").and_then(|id| id.as_str()) else {
+        let value = object
+            .get("value")
+            .or_else(|| object.get("description"))
+            .cloned()
+            .unwrap_or_default();
+        return Ok((value, false));
+    };
+
+    let by_value = cdp
+        .send(
+            "Runtime.callFunctionOn",
+            serde_json::json!({
+                "objectId": object_id,
+                "functionDeclaration": "function() { return this; }",
+                "returnByValue": true,
+            }),
+        )
+        .await;
+    let value = by_value
+        .ok()
+        .filter(|r| r.get("exceptionDetails").is_none())
+        .and_then(|r| r.pointer("/result/value").cloned())
+        .filter(|v| v.to_string().len() <= MAX_EVAL_RESULT_BYTES);
+    cdp.send(
+        "Runtime.releaseObjectGroup",
+        serde_json::json!({ "objectGroup": "browser-cli-eval" }),
+    )
+    .await?;
+    match value {
+        Some(value) => Ok((value, false)),
+        None => Ok((serde_json::json!(render_object_preview(&object)), true)),
+    }
+}
+
+/// Render a `Runtime.RemoteObject` preview as `Object {a: 1, b: "x", …}`.
+fn render_object_preview(object: &serde_json::Value) -> String {
+    let description = object
+        .get("description")
+        .and_then(|d| d.as_str())
+        .unwrap_or("Object");
+    let Some(preview) = object.get("preview") else {
+        return description.to_string();
+    };
+    let is_array = preview.get("subtype").and_then(|s| s.as_str()) == Some("array");
+    let mut entries: Vec<String> = preview
+        .get("properties")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .map(|prop| {
+            let name = prop
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let value = prop
+                .get("value")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let value = if prop.get("type").and_then(|t| t.as_str()) == Some("string") {
+                serde_json::json!(value).to_string()
+            } else {
+                value.to_string()
+            };
+            if is_array && name.parse::<usize>().is_ok() {
+                value
+            } else {
+                format!("{}: {}", name, value)
+            }
+        })
+        .collect();
+    if preview.get("overflow").and_then(|o| o.as_bool()) == Some(true) {
+        entries.push("…".to_string());
+    }
+    if is_array {
+        format!("{} [{}]", description, entries.join(", "))
+    } else {
+        format!("{} {{{}}}", description, entries.join(", "))
+    }
+}
+
+/// Options for `poll`
+pub struct PollOptions {
+    pub until: Option<String>,
+    pub interval_ms: u64,
+    pub max: Option<u64>,
+}
+
+fn build_poll_script(script: &str, until: Option<&str>) -> String {
+    format!(
+        "(() => {{ const value = ({}); return {{ value, done: !!({}) }}; }})()",
+        script,
+        until.unwrap_or("false")
+    )
+}
+
+/// Evaluate `script` every interval, printing each result, until `--until` holds
+/// (with the latest result bound to `value`) or `--max` polls have run.
+pub async fn cmd_poll(port: u16, script: &str, opts: &PollOptions, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let expression = build_poll_script(script, opts.until.as_deref());
+    let mut count = 0u64;
+    loop {
+        let result = cdp.eval(&expression).await?;
+        count += 1;
+        let value = result.get("value").cloned().unwrap_or_default();
+        if json {
+            println!("{}", serde_json::json!({ "poll": count, "value": value }));
+        } else {
+            println!("{}", serde_json::to_string(&value)?);
+        }
+        if result.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            return Ok(());
+        }
+        if opts.max.is_some_and(|max| count >= max) {
+            return match &opts.until {
+                Some(until) => Err(anyhow!(
+                    "Condition `{}` not met after {} polls",
+                    until,
+                    count
+                )),
+                None => Ok(()),
+            };
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(opts.interval_ms)).await;
+    }
+}
+
+/// Create an isolated world in the main frame: it shares the DOM but not the
+/// page's JS globals, and is not subject to the page's CSP.
+async fn create_isolated_world(cdp: &mut CdpConnection) -> Result<i64> {
+    let tree = cdp.send("Page.getFrameTree", serde_json::json!({})).await?;
+    let frame_id = tree
+        .pointer("/frameTree/frame/id")
+        .and_then(|id| id.as_str())
+        .context("No main frame")?;
+    let world = cdp
+        .send(
+            "Page.createIsolatedWorld",
+            serde_json::json!({ "frameId": frame_id, "worldName": "browser-cli" }),
+        )
+        .await?;
+    world
+        .get("executionContextId")
+        .and_then(|id| id.as_i64())
+        .context("Page.createIsolatedWorld returned no context")
+}
+
+pub async fn cmd_snapshot(
+    port: u16,
+    opts: &SnapshotOptions,
+    max_chars: Option<usize>,
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let output = snapshot::take_snapshot(&mut cdp, opts).await?;
+    println!("{}", truncate_output(&output, max_chars));
+    Ok(())
+}
+
+/// Cut output to `max_chars`, backing up to a line boundary when one is available.
+pub(crate) fn truncate_output(text: &str, max_chars: Option<usize>) -> String {
+    let Some(max_chars) = max_chars else {
+        return text.to_string();
+    };
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map(|(idx, _)| idx)
+        .unwrap_or(text.len());
+    let kept = match text[..cut].rfind('\n') {
+        Some(newline) if newline > 0 => &text[..newline],
+        _ => &text[..cut],
+    };
+    let dropped = total - kept.chars().count();
+    format!("{}\n... (truncated {} chars)", kept, dropped)
+}
+
+pub async fn cmd_get(port: u16, what: &crate::GetCommand, json: bool) -> Result<()> {
+    let targets = cdp::get_targets(port).await?;
+    let target = cdp::find_active_target(&targets)?;
+    match what {
+        crate::GetCommand::Title => {
+            print_field(json, "title", &target.title);
+            return Ok(());
+        }
+        crate::GetCommand::Url => {
+            print_field(json, "url", &target.url);
+            return Ok(());
+        }
+        _ => {}
+    }
+    let ws = target
+        .webSocketDebuggerUrl
+        .as_ref()
+        .context("Active target has no webSocketDebuggerUrl")?;
+    let mut cdp = CdpConnection::connect(ws).await?;
+    if let Some((selector, nth)) = what.element_target() {
+        await_element(&mut cdp, selector, nth).await?;
+    }
+
+    match what {
+        crate::GetCommand::Title | crate::GetCommand::Url => {}
+        crate::GetCommand::Text {
+            selector: Some(selector),
+            all: true,
+            join,
+            max_chars,
+            trim,
+            visible_only,
+            ..
+        } => {
+            let text_of = if *visible_only {
+                include_str!("visible_text.js").replace("__ROOT__", "el")
+            } else {
+                "el.innerText || ''".to_string()
+            };
+            let script = format!(
+                "Array.from({}).map(el => {})",
+                selector::all_expr(selector)?,
+                text_of
+            );
+            let result = cdp.eval(&script).await?;
+            let texts: Vec<String> = result
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|text| {
+                    let text = text.as_str().unwrap_or_default();
+                    if *trim {
+                        tidy_whitespace(text)
+                    } else {
+                        text.to_string()
+                    }
+                })
+                .collect();
+            if json {
+                crate::output::print_list(&texts)?;
+            } else {
+                let joined = texts.join(&unescape_separator(join));
+                println!("{}", truncate_output(&joined, *max_chars));
+            }
+        }
+        crate::GetCommand::Text {
+            selector,
+            nth,
+            max_chars,
+            trim,
+            markdown,
+            visible_only,
+            ..
+        } => {
+            let script = if *markdown {
+                build_markdown_script(selector, *nth)?
+            } else if *visible_only {
+                build_visible_text_script(selector, *nth)?
+            } else {
+                build_text_script(selector, *nth)?
+            };
+            let text = cdp.eval(&script).await?;
+            if let Some(text) = text.as_str() {
+                let text = if *trim {
+                    tidy_whitespace(text)
+                } else {
+                    text.to_string()
+                };
+                println!("{}", truncate_output(&text, *max_chars));
+            }
+        }
+        crate::GetCommand::Html {
+            selector,
+            nth: _,
+            outer,
+            all: true,
+            max_chars,
+        } => {
+            let field = if *outer { "outerHTML" } else { "innerHTML" };
+            let script = format!(
+                "Array.from({}).map(el => el.{})",
+                selector::all_expr(selector)?,
+                field
+            );
+            let result = cdp.eval(&script).await?;
+            let entries: Vec<String> = result
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|html| truncate_output(html.as_str().unwrap_or_default(), *max_chars))
+                .collect();
+            if json {
+                crate::output::print_list(&entries)?;
+            } else {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
+        }
+        crate::GetCommand::Html {
+            selector,
+            nth,
+            outer: true,
+            max_chars,
+            ..
+        }
+        | crate::GetCommand::OuterHtml {
+            selector,
+            nth,
+            max_chars,
+        } => {
+            let node_id = query_node_id(&mut cdp, selector, *nth).await?;
+            let result = cdp
+                .send("DOM.getOuterHTML", serde_json::json!({ "nodeId": node_id }))
+                .await?;
+            let html = result
+                .get("outerHTML")
+                .and_then(|h| h.as_str())
+                .unwrap_or_default();
+            println!("{}", truncate_output(html, *max_chars));
+        }
+        crate::GetCommand::Html {
+            selector,
+            nth,
+            max_chars,
+            ..
+        } => {
+            let script = format!("{}?.innerHTML || ''", element_expr(selector, *nth)?);
+            let html = cdp.eval(&script).await?;
+            println!(
+                "{}",
+                truncate_output(html.as_str().unwrap_or_default(), *max_chars)
+            );
+        }
+        crate::GetCommand::Value { selector, nth } => {
+            let script =
+                VALUE_SCRIPT_TEMPLATE.replace("__ELEMENT__", &element_expr(selector, *nth)?);
+            let result = cdp.eval(&script).await?;
+            print_value(json, &result);
+        }
+        crate::GetCommand::Attr {
+            selector,
+            name,
+            nth,
+        } => {
+            eval_selector_attr(&mut cdp, selector, *nth, name).await?;
+        }
+        crate::GetCommand::Aria { selector, nth } => {
+            let summary = crate::snapshot::describe_ax_element(&mut cdp, selector, *nth).await?;
+            print_ax_summary(json, &summary);
+        }
+        crate::GetCommand::Count { selector, settle } => {
+            eval_selector_count(&mut cdp, selector, *settle).await?;
+        }
+        crate::GetCommand::Selector { ref_id } => {
+            let element = crate::refs::resolve_ref(port, ref_id).await?;
+            let script = REF_SELECTOR_SCRIPT_TEMPLATE
+                .replace("__ELEMENT__", &selector::first_expr(&element)?);
+            print_eval_str(&mut cdp, &script).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn eval_selector_attr(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+    name: &str,
+) -> Result<()> {
+    let script = format!(
+        "{}?.getAttribute({}) || ''",
+        element_expr(selector, nth)?,
+        serde_json::to_string(name)?
+    );
+    print_eval_str(cdp, &script).await
+}
+
+async fn eval_selector_count(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    settle: Option<u64>,
+) -> Result<()> {
+    let script = format!("{}.length", selector::all_expr(selector)?);
+    let result = settled_eval(cdp, &script, settle).await?;
+    println!("{}", result);
+    Ok(())
+}
+
+/// Readings `settled_eval` takes before giving up on the page holding still
+const MAX_SETTLE_READINGS: u32 = 10;
+
+/// Evaluate `script`; with `settle`, keep re-reading `settle` ms apart until two
+/// consecutive readings agree, so a list that is still rendering isn't counted
+/// halfway through. Gives up after `MAX_SETTLE_READINGS` and returns the last one.
+async fn settled_eval(
+    cdp: &mut CdpConnection,
+    script: &str,
+    settle: Option<u64>,
+) -> Result<serde_json::Value> {
+    let mut reading = cdp.eval(script).await?;
+    let Some(ms) = settle else {
+        return Ok(reading);
+    };
+    for _ in 1..MAX_SETTLE_READINGS {
+        tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+        let next = cdp.eval(script).await?;
+        if next == reading {
+            return Ok(reading);
+        }
+        reading = next;
+    }
+    eprintln!(
+        "note: result still changing after {} readings; using the last one",
+        MAX_SETTLE_READINGS
+    );
+    Ok(reading)
+}
+
+pub async fn cmd_exists(port: u16, selector: &str, settle: Option<u64>, json: bool) -> Result<()> {
+    let script = format!("({}) != null", selector::first_expr(selector)?);
+    let mut cdp = cdp::connect_active(port).await?;
+    let exists = settled_eval(&mut cdp, &script, settle).await?.as_bool() == Some(true);
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "selector": selector, "exists": exists })
+        );
+    } else {
+        println!("{}", exists);
+    }
+    Ok(())
+}
+
+fn build_text_script(selector: &Option<String>, nth: Option<usize>) -> Result<String> {
     Ok(match selector {
-        Some(sel) => format!(
-            "document.querySelector({})?.innerText || ''",
-            serde_json::to_string(sel)?
-        ),
+        Some(sel) => format!("{}?.innerText || ''", element_expr(sel, nth)?),
         None => "document.body.innerText".to_string(),
     })
 }
 
-async fn eval_and_print_str(ws_url: &str, script: &str) -> Result<()> {
-    let mut cdp = CdpConnection::connect(ws_url).await?;
-    print_eval_str(&mut cdp, script).await
+/// Text of rendered, non-`aria-hidden` elements only, one line per block.
+fn build_visible_text_script(selector: &Option<String>, nth: Option<usize>) -> Result<String> {
+    let root = match selector {
+        Some(sel) => element_expr(sel, nth)?,
+        None => "document.body".to_string(),
+    };
+    Ok(include_str!("visible_text.js").replace("__ROOT__", &root))
+}
+
+fn build_markdown_script(selector: &Option<String>, nth: Option<usize>) -> Result<String> {
+    let root = match selector {
+        Some(sel) => element_expr(sel, nth)?,
+        None => "document.body".to_string(),
+    };
+    Ok(include_str!("markdown.js").replace("__ROOT__", &root))
+}
+
+/// Expand `\t`, `\n` and `\\` in a separator typed on the command line.
+pub(crate) fn unescape_separator(sep: &str) -> String {
+    let mut out = String::new();
+    let mut chars = sep.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Collapse runs of spaces/tabs, trim each line, and squeeze blank lines to one.
+pub(crate) fn tidy_whitespace(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && out.last().is_none_or(|prev| prev.is_empty()) {
+            continue;
+        }
+        out.push(line);
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// Print a `VALUE_SCRIPT_TEMPLATE` result: booleans for checkboxes/radios, one
+/// line per selected option for multi-selects, the plain value otherwise.
+fn print_value(json: bool, result: &serde_json::Value) {
+    let value = result.get("value").cloned().unwrap_or_default();
+    if json {
+        let kind = result
+            .get("kind")
+            .and_then(|k| k.as_str())
+            .unwrap_or("value");
+        println!("{}", serde_json::json!({ "value": value, "type": kind }));
+        return;
+    }
+    match value {
+        serde_json::Value::String(text) => println!("{}", text),
+        serde_json::Value::Array(values) => {
+            for v in values {
+                println!("{}", v.as_str().unwrap_or_default());
+            }
+        }
+        other => println!("{}", other),
+    }
+}
+
+fn print_ax_summary(json: bool, summary: &serde_json::Value) {
+    if json {
+        println!("{}", summary);
+        return;
+    }
+    let fields = summary.as_object().into_iter().flatten();
+    let (head, states): (Vec<_>, Vec<_>) = fields.partition(|(k, _)| *k == "role" || *k == "name");
+    for (key, value) in head.into_iter().rev().chain(states) {
+        match value {
+            serde_json::Value::String(text) => println!("{}: {}", key, text),
+            other => println!("{}: {}", key, other),
+        }
+    }
 }
 
 fn print_field(json: bool, key: &str, value: &str) {
@@ -359,15 +1686,35 @@ pub async fn cmd_tabs(port: u16, action: &crate::TabsCommand, json: bool) -> Res
     let targets = cdp::get_targets(port).await?;
 
     match action {
-        crate::TabsCommand::List => print_tab_list(&targets, json)?,
-        crate::TabsCommand::New { url } => {
-            create_tab(&targets, url.as_deref()).await?;
+        crate::TabsCommand::List { all: false } => print_tab_list(&targets, json)?,
+        crate::TabsCommand::List { all: true } => print_all_targets(port, json).await?,
+        crate::TabsCommand::New { url, background } => {
+            create_tab(port, &targets, url.as_deref(), *background, json).await?;
         }
-        crate::TabsCommand::Close { index } => {
-            close_tab(&targets, index.unwrap_or(0)).await?;
+        crate::TabsCommand::Close {
+            index: None,
+            all,
+            pattern,
+            force,
+        } if *all || pattern.is_some() => {
+            close_tabs(port, &targets, pattern.as_deref(), *force, json).await?;
+        }
+        crate::TabsCommand::Close { index, .. } => {
+            close_tab(&targets, index.unwrap_or(0), json).await?;
         }
         crate::TabsCommand::Switch { index } => {
-            switch_tab(&targets, *index).await?;
+            switch_tab(&targets, *index, json).await?;
+        }
+        crate::TabsCommand::Move {
+            index,
+            to_new_window,
+        } => {
+            if !to_new_window {
+                return Err(anyhow!(
+                    "CDP cannot reorder tabs within a window; use --to-new-window"
+                ));
+            }
+            move_tab_to_new_window(port, &targets, *index, json).await?;
         }
     }
     Ok(())
@@ -379,8 +1726,7 @@ fn print_tab_list(targets: &[cdp::TargetJson], json: bool) -> Result<()> {
             .iter()
             .map(|t| serde_json::json!({ "title": t.title, "url": t.url, "id": t.id }))
             .collect();
-        println!("{}", serde_json::to_string_pretty(&tabs)?);
-        return Ok(());
+        return crate::output::print_list(&tabs);
     }
     for (i, target) in targets.iter().enumerate() {
         println!("{}: {} - {}", i, target.title, target.url);
@@ -388,16 +1734,88 @@ fn print_tab_list(targets: &[cdp::TargetJson], json: bool) -> Result<()> {
     Ok(())
 }
 
-async fn create_tab(targets: &[cdp::TargetJson], url: Option<&str>) -> Result<()> {
+async fn print_all_targets(port: u16, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_browser(port).await?;
+    let result = cdp.send("Target.getTargets", serde_json::json!({})).await?;
+    let infos = result
+        .get("targetInfos")
+        .and_then(|t| t.as_array())
+        .context("Target.getTargets did not return targetInfos")?;
+    let field = |info: &serde_json::Value, key: &str| {
+        info.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    if json {
+        let targets: Vec<_> = infos
+            .iter()
+            .map(|info| {
+                serde_json::json!({
+                    "type": field(info, "type"),
+                    "title": field(info, "title"),
+                    "url": field(info, "url"),
+                    "id": field(info, "targetId"),
+                })
+            })
+            .collect();
+        return crate::output::print_list(&targets);
+    }
+    for (i, info) in infos.iter().enumerate() {
+        println!(
+            "{}: [{}] {} - {}",
+            i,
+            field(info, "type"),
+            field(info, "title"),
+            field(info, "url")
+        );
+    }
+    Ok(())
+}
+
+async fn create_tab(
+    port: u16,
+    targets: &[cdp::TargetJson],
+    url: Option<&str>,
+    background: bool,
+    json: bool,
+) -> Result<()> {
     let mut cdp = connect_target_session(targets).await?;
     let url = url.unwrap_or("about:blank");
-    cdp.send("Target.createTarget", serde_json::json!({ "url": url }))
+    let created = cdp
+        .send(
+            "Target.createTarget",
+            serde_json::json!({ "url": url, "background": background }),
+        )
         .await?;
-    println!("✓ New tab created");
+    let target_id = created
+        .get("targetId")
+        .and_then(|id| id.as_str())
+        .context("Target.createTarget did not return a targetId")?;
+    if !background {
+        cdp.send(
+            "Target.activateTarget",
+            serde_json::json!({ "targetId": target_id }),
+        )
+        .await?;
+    }
+
+    let targets = cdp::get_targets(port).await?;
+    let index = targets.iter().position(|t| t.id == target_id);
+    let message = match index {
+        Some(idx) => format!("New tab created at index {}", idx),
+        None => "New tab created".to_string(),
+    };
+    print_action(
+        json,
+        "tabs.new",
+        serde_json::json!({ "index": index, "id": target_id }),
+        &message,
+    );
     Ok(())
 }
 
-async fn close_tab(targets: &[cdp::TargetJson], idx: usize) -> Result<()> {
+async fn close_tab(targets: &[cdp::TargetJson], idx: usize, json: bool) -> Result<()> {
     let target = targets.get(idx).context("Tab index out of range")?;
     let mut cdp = connect_target_session(targets).await?;
     cdp.send(
@@ -405,11 +1823,52 @@ async fn close_tab(targets: &[cdp::TargetJson], idx: usize) -> Result<()> {
         serde_json::json!({ "targetId": target.id }),
     )
     .await?;
-    println!("✓ Tab closed");
+    print_action(
+        json,
+        "tabs.close",
+        serde_json::json!({ "index": idx, "id": target.id }),
+        "Tab closed",
+    );
     Ok(())
 }
 
-async fn switch_tab(targets: &[cdp::TargetJson], idx: usize) -> Result<()> {
+/// Internal pages that bulk close leaves alone unless forced.
+pub(crate) fn is_internal_url(url: &str) -> bool {
+    url.starts_with("about:") || url.starts_with("chrome://")
+}
+
+/// Close every page tab, or those whose URL contains `pattern`.
+async fn close_tabs(
+    port: u16,
+    targets: &[cdp::TargetJson],
+    pattern: Option<&str>,
+    force: bool,
+    json: bool,
+) -> Result<()> {
+    let selected: Vec<&cdp::TargetJson> = targets
+        .iter()
+        .filter(|t| pattern.is_none_or(|p| t.url.contains(p)))
+        .filter(|t| force || !is_internal_url(&t.url))
+        .collect();
+    let mut cdp = cdp::connect_browser(port).await?;
+    for target in &selected {
+        cdp.send(
+            "Target.closeTarget",
+            serde_json::json!({ "targetId": target.id }),
+        )
+        .await?;
+    }
+    let ids: Vec<&str> = selected.iter().map(|t| t.id.as_str()).collect();
+    print_action(
+        json,
+        "tabs.close",
+        serde_json::json!({ "closed": ids }),
+        &format!("Closed {} tabs", ids.len()),
+    );
+    Ok(())
+}
+
+async fn switch_tab(targets: &[cdp::TargetJson], idx: usize, json: bool) -> Result<()> {
     let target = targets.get(idx).context("Tab index out of range")?;
     let mut cdp = connect_target_session(targets).await?;
     cdp.send(
@@ -417,7 +1876,57 @@ async fn switch_tab(targets: &[cdp::TargetJson], idx: usize) -> Result<()> {
         serde_json::json!({ "targetId": target.id }),
     )
     .await?;
-    println!("✓ Switched to tab {}: {}", idx, target.title);
+    print_action(
+        json,
+        "tabs.switch",
+        serde_json::json!({ "index": idx, "id": target.id }),
+        &format!("Switched to tab {}: {}", idx, target.title),
+    );
+    Ok(())
+}
+
+/// CDP has no call to re-parent a tab, so open its URL in a new window and close
+/// the original. Page state (scroll position, form input, JS heap) is not carried over.
+async fn move_tab_to_new_window(
+    port: u16,
+    targets: &[cdp::TargetJson],
+    idx: usize,
+    json: bool,
+) -> Result<()> {
+    let target = targets.get(idx).context("Tab index out of range")?;
+    let mut cdp = cdp::connect_browser(port).await?;
+    let created = cdp
+        .send(
+            "Target.createTarget",
+            serde_json::json!({ "url": target.url, "newWindow": true }),
+        )
+        .await?;
+    let new_id = created
+        .get("targetId")
+        .and_then(|id| id.as_str())
+        .context("Target.createTarget did not return a targetId")?
+        .to_string();
+    let window = cdp
+        .send(
+            "Browser.getWindowForTarget",
+            serde_json::json!({ "targetId": new_id }),
+        )
+        .await?;
+    let window_id = window.get("windowId").cloned().unwrap_or_default();
+    cdp.send(
+        "Target.closeTarget",
+        serde_json::json!({ "targetId": target.id }),
+    )
+    .await?;
+    print_action(
+        json,
+        "tabs.move",
+        serde_json::json!({ "index": idx, "id": new_id, "windowId": window_id }),
+        &format!(
+            "Moved tab {} to new window {}: {}",
+            idx, window_id, target.url
+        ),
+    );
     Ok(())
 }
 
@@ -431,32 +1940,382 @@ pub async fn cmd_wait(
     target: Option<String>,
     url: Option<String>,
     load: Option<String>,
+    function: Option<String>,
+    stable: Option<u64>,
+    json: bool,
 ) -> Result<()> {
     let mut cdp = cdp::connect_active(port).await?;
 
+    if let Some(quiet_ms) = stable {
+        let script = WAIT_STABLE_SCRIPT_TEMPLATE.replace("__QUIET_MS__", &quiet_ms.to_string());
+        let waited = eval_promise(&mut cdp, &script).await?;
+        print_action(
+            json,
+            "wait",
+            serde_json::json!({ "stable": quiet_ms, "waitedMs": waited }),
+            &format!("Page stable after {}ms", waited),
+        );
+        return Ok(());
+    }
+
+    if let Some(expression) = function {
+        let script = WAIT_FUNCTION_SCRIPT_TEMPLATE.replace("__EXPRESSION__", &expression);
+        eval_promise(&mut cdp, &script).await?;
+        print_action(
+            json,
+            "wait",
+            serde_json::json!({ "function": expression }),
+            "Condition met",
+        );
+        return Ok(());
+    }
+
     if let Some(ms) = target.as_ref().and_then(|s| s.parse::<u64>().ok()) {
         tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
-        println!("✓ Waited {}ms", ms);
+        print_action(
+            json,
+            "wait",
+            serde_json::json!({ "ms": ms }),
+            &format!("Waited {}ms", ms),
+        );
         return Ok(());
     }
     if let Some(selector) = target {
         wait_for_selector(&mut cdp, &selector).await?;
-        println!("✓ Element found");
+        print_action(
+            json,
+            "wait",
+            serde_json::json!({ "selector": selector }),
+            "Element found",
+        );
         return Ok(());
     }
     if url.is_some() {
         println!("URL wait not implemented");
         return Ok(());
     }
-    if load.is_some() {
-        println!("Load state wait not implemented");
+    if let Some(state) = load {
+        match crate::network::network_idle_threshold(&state) {
+            Some(max_inflight) => {
+                let mut watcher = crate::network::NetworkIdleWatcher::start(cdp).await?;
+                watcher.wait(max_inflight).await?;
+            }
+            None => wait_for_load_state(&mut cdp, &state).await?,
+        }
+        print_action(
+            json,
+            "wait",
+            serde_json::json!({ "load": state }),
+            &format!("Reached {}", state),
+        );
+    }
+    Ok(())
+}
+
+/// One of the alternatives `wait --selector/--text` races
+#[derive(Clone, Debug)]
+pub enum WaitCondition {
+    Selector(String),
+    Text(String),
+}
+
+impl WaitCondition {
+    fn kind(&self) -> &'static str {
+        match self {
+            WaitCondition::Selector(_) => "selector",
+            WaitCondition::Text(_) => "text",
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            WaitCondition::Selector(value) | WaitCondition::Text(value) => value,
+        }
+    }
+
+    fn predicate(&self) -> Result<String> {
+        Ok(match self {
+            WaitCondition::Selector(selector) => {
+                format!("() => {}", selector::first_expr(selector)?)
+            }
+            WaitCondition::Text(text) => format!(
+                "() => (document.body?.innerText || '').includes({})",
+                serde_json::to_string(text)?
+            ),
+        })
     }
+}
+
+fn build_wait_any_script(conditions: &[WaitCondition], timeout_ms: u64) -> Result<String> {
+    let predicates = conditions
+        .iter()
+        .map(WaitCondition::predicate)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(WAIT_ANY_SCRIPT_TEMPLATE
+        .replace("__CONDITIONS__", &predicates.join(", "))
+        .replace("__TIMEOUT__", &timeout_ms.to_string()))
+}
+
+/// Check every condition each tick and report the first one that holds.
+pub async fn cmd_wait_any(
+    port: u16,
+    conditions: &[WaitCondition],
+    timeout_ms: u64,
+    json: bool,
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let script = build_wait_any_script(conditions, timeout_ms)?;
+    let hit = eval_promise(&mut cdp, &script).await?;
+    let condition = hit
+        .as_u64()
+        .and_then(|i| conditions.get(i as usize))
+        .context("Wait returned no matching condition")?;
+    print_action(
+        json,
+        "wait",
+        serde_json::json!({ "matched": { condition.kind(): condition.value() } }),
+        &format!("Matched {} {}", condition.kind(), condition.value()),
+    );
+    Ok(())
+}
+
+async fn wait_for_load_state(cdp: &mut CdpConnection, state: &str) -> Result<()> {
+    let condition = match state {
+        "load" => "document.readyState === 'complete'",
+        "domcontentloaded" => "document.readyState !== 'loading'",
+        _ => {
+            return Err(anyhow!(
+                "Unknown load state '{}' (expected load, domcontentloaded, networkidle0 or networkidle2)",
+                state
+            ));
+        }
+    };
+    let script = WAIT_LOAD_SCRIPT_TEMPLATE.replace("__CONDITION__", condition);
+    eval_promise(cdp, &script).await?;
     Ok(())
 }
 
 async fn wait_for_selector(cdp: &mut CdpConnection, selector: &str) -> Result<()> {
-    let quoted_selector = serde_json::to_string(selector)?;
-    let script = WAIT_SELECTOR_SCRIPT_TEMPLATE.replace("__SELECTOR__", &quoted_selector);
-    cdp.eval(&script).await?;
+    let script =
+        WAIT_SELECTOR_SCRIPT_TEMPLATE.replace("__ELEMENT__", &selector::first_expr(selector)?);
+    eval_promise(cdp, &script).await?;
     Ok(())
 }
+
+/// Evaluate a promise-returning script, awaiting it and surfacing rejections as errors.
+pub(crate) async fn eval_promise(
+    cdp: &mut CdpConnection,
+    script: &str,
+) -> Result<serde_json::Value> {
+    let result = cdp
+        .send(
+            "Runtime.evaluate",
+            serde_json::json!({
+                "expression": script,
+                "awaitPromise": true,
+                "returnByValue": true,
+            }),
+        )
+        .await?;
+    if let Some(details) = result.get("exceptionDetails") {
+        return Err(anyhow!("{}", cdp::exception_message(details)));
+    }
+    Ok(result
+        .get("result")
+        .and_then(|r| r.get("value"))
+        .cloned()
+        .unwrap_or(serde_json::Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        WaitCondition, build_eval_function, build_input_value_script, build_poll_script,
+        build_wait_any_script, element_expr, is_internal_url, numbered_path, parse_eval_arg,
+        render_object_preview, resolve_user_agent, tidy_whitespace, truncate_output,
+        unescape_separator,
+    };
+
+    #[test]
+    fn numbered_path_inserts_index_before_extension() {
+        use std::path::Path;
+
+        assert_eq!(
+            numbered_path(Path::new("/tmp/cards/card.jpg"), 2),
+            Path::new("/tmp/cards/card-2.jpg")
+        );
+        assert_eq!(numbered_path(Path::new("card"), 0), Path::new("card-0"));
+    }
+
+    #[test]
+    fn wait_any_script_lists_predicates_in_order() {
+        let script = build_wait_any_script(
+            &[
+                WaitCondition::Selector(".toast".to_string()),
+                WaitCondition::Text("Invalid \"email\"".to_string()),
+            ],
+            5000,
+        )
+        .unwrap();
+        let selector_at = script.find(".toast").unwrap();
+        let text_at = script.find("includes(\"Invalid \\\"email\\\"\")").unwrap();
+        assert!(selector_at < text_at);
+        assert!(script.contains("Date.now() + 5000"));
+    }
+
+    #[test]
+    fn clear_first_empties_the_field_before_appending() {
+        let script = build_input_value_script("#q", None, "shoes", true, true).unwrap();
+        let cleared_at = script.find("setter.call(el, '')").unwrap();
+        let appended_at = script.find("el.value += \"shoes\"").unwrap();
+        assert!(script.contains("if (true)"));
+        assert!(cleared_at < appended_at);
+
+        let script = build_input_value_script("#q", None, "shoes", false, false).unwrap();
+        assert!(script.contains("if (false)"));
+        assert!(script.contains("el.value = \"shoes\""));
+    }
+
+    #[test]
+    fn separator_escapes_expand() {
+        assert_eq!(unescape_separator("\\t"), "\t");
+        assert_eq!(unescape_separator(", "), ", ");
+        assert_eq!(unescape_separator("\\n---\\n"), "\n---\n");
+        assert_eq!(unescape_separator("a\\\\b"), "a\\b");
+    }
+
+    #[test]
+    fn eval_args_parse_json_with_string_fallback() {
+        assert_eq!(
+            parse_eval_arg("n=3").unwrap(),
+            ("n".to_string(), serde_json::json!(3))
+        );
+        assert_eq!(
+            parse_eval_arg("opts={\"a\":[1]}").unwrap().1,
+            serde_json::json!({ "a": [1] })
+        );
+        assert_eq!(
+            parse_eval_arg("q=hello world").unwrap().1,
+            serde_json::json!("hello world")
+        );
+        assert!(parse_eval_arg("novalue").is_err());
+        assert!(parse_eval_arg("1x=2").is_err());
+    }
+
+    #[test]
+    fn eval_function_binds_arg_names() {
+        let args = vec![
+            ("n".to_string(), serde_json::json!(3)),
+            ("sel".to_string(), serde_json::json!("li")),
+        ];
+        assert_eq!(
+            build_eval_function("return n;", &args),
+            "async function(n, sel) {\nreturn n;\n}"
+        );
+    }
+
+    #[test]
+    fn object_preview_renders_properties_and_overflow() {
+        let object = serde_json::json!({
+            "type": "object",
+            "description": "Object",
+            "preview": {
+                "type": "object",
+                "overflow": true,
+                "properties": [
+                    { "name": "id", "type": "number", "value": "7" },
+                    { "name": "label", "type": "string", "value": "a\"b" },
+                    { "name": "self", "type": "object", "value": "Object" }
+                ]
+            }
+        });
+        assert_eq!(
+            render_object_preview(&object),
+            "Object {id: 7, label: \"a\\\"b\", self: Object, …}"
+        );
+
+        let array = serde_json::json!({
+            "description": "Array(2)",
+            "preview": {
+                "subtype": "array",
+                "overflow": false,
+                "properties": [
+                    { "name": "0", "type": "number", "value": "1" },
+                    { "name": "1", "type": "string", "value": "x" }
+                ]
+            }
+        });
+        assert_eq!(render_object_preview(&array), "Array(2) [1, \"x\"]");
+    }
+
+    #[test]
+    fn poll_script_binds_value_for_until() {
+        assert_eq!(
+            build_poll_script("window.count", Some("value > 3")),
+            "(() => { const value = (window.count); return { value, done: !!(value > 3) }; })()"
+        );
+        assert!(build_poll_script("1", None).contains("done: !!(false)"));
+    }
+
+    #[test]
+    fn tidy_whitespace_collapses_runs_and_blank_lines() {
+        let text = "  Title \t here \n\n\n\n  body   text\n \n";
+        assert_eq!(tidy_whitespace(text), "Title here\n\nbody text");
+    }
+
+    #[test]
+    fn internal_urls_are_detected() {
+        assert!(is_internal_url("about:blank"));
+        assert!(is_internal_url("chrome://newtab/"));
+        assert!(!is_internal_url("https://example.com/about:blank"));
+    }
+
+    #[test]
+    fn element_expr_selects_first_or_nth_match() {
+        assert_eq!(
+            element_expr("a.b", None).unwrap(),
+            r#"document.querySelector("a.b")"#
+        );
+        let nth = element_expr("li", Some(2)).unwrap();
+        assert!(nth.contains(r#"document.querySelectorAll("li")"#));
+        assert!(nth.contains("all.length <= 2"));
+        assert!(nth.contains("return all[2]"));
+    }
+
+    #[test]
+    fn resolves_user_agent_presets() {
+        let (ua, platform) = resolve_user_agent("iPhone");
+        assert!(ua.contains("iPhone OS"));
+        assert_eq!(platform, Some("iPhone"));
+
+        let (ua, platform) = resolve_user_agent("googlebot");
+        assert!(ua.contains("Googlebot"));
+        assert_eq!(platform, None);
+
+        assert_eq!(resolve_user_agent("MyBot/1.0"), ("MyBot/1.0", None));
+    }
+
+    #[test]
+    fn truncate_output_leaves_short_text_alone() {
+        assert_eq!(truncate_output("short", Some(10)), "short");
+        assert_eq!(truncate_output("no limit", None), "no limit");
+    }
+
+    #[test]
+    fn truncate_output_cuts_on_line_boundary() {
+        let text = "line one\nline two\nline three";
+
+        assert_eq!(
+            truncate_output(text, Some(12)),
+            "line one\n... (truncated 20 chars)"
+        );
+    }
+
+    #[test]
+    fn truncate_output_cuts_mid_line_without_newline() {
+        assert_eq!(
+            truncate_output("abcdefghij", Some(4)),
+            "abcd\n... (truncated 6 chars)"
+        );
+    }
+}
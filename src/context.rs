@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::cdp;
+use crate::output::print_action;
+
+/// Contexts created by `context new`, kept per port so `context dispose` can find them.
+fn contexts_file(port: u16) -> PathBuf {
+    PathBuf::from(format!("/tmp/browser-cli-contexts-{}.json", port))
+}
+
+pub(crate) fn load_contexts(port: u16) -> Vec<String> {
+    std::fs::read_to_string(contexts_file(port))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_contexts(port: u16, contexts: &[String]) -> Result<()> {
+    let path = contexts_file(port);
+    if contexts.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(());
+    }
+    std::fs::write(&path, serde_json::to_string(contexts)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub async fn cmd_context(port: u16, action: &crate::ContextCommand, json: bool) -> Result<()> {
+    match action {
+        crate::ContextCommand::New { url } => {
+            create_context(port, url.as_deref().unwrap_or("about:blank"), json).await
+        }
+        crate::ContextCommand::List => list_contexts(port, json),
+        crate::ContextCommand::Dispose { id } => dispose_contexts(port, id.as_deref(), json).await,
+    }
+}
+
+async fn create_context(port: u16, url: &str, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_browser(port).await?;
+    let created = cdp
+        .send("Target.createBrowserContext", serde_json::json!({}))
+        .await?;
+    let context_id = created
+        .get("browserContextId")
+        .and_then(Value::as_str)
+        .context("Target.createBrowserContext did not return an id")?
+        .to_string();
+    let target = cdp
+        .send(
+            "Target.createTarget",
+            serde_json::json!({ "url": url, "browserContextId": context_id }),
+        )
+        .await?;
+
+    let mut contexts = load_contexts(port);
+    contexts.push(context_id.clone());
+    save_contexts(port, &contexts)?;
+
+    print_action(
+        json,
+        "context.new",
+        serde_json::json!({ "contextId": context_id, "targetId": target.get("targetId") }),
+        &format!("Created context {}", context_id),
+    );
+    Ok(())
+}
+
+fn list_contexts(port: u16, json: bool) -> Result<()> {
+    let contexts = load_contexts(port);
+    if json {
+        return crate::output::print_list(&contexts);
+    }
+    for id in &contexts {
+        println!("{}", id);
+    }
+    Ok(())
+}
+
+async fn dispose_contexts(port: u16, id: Option<&str>, json: bool) -> Result<()> {
+    let mut contexts = load_contexts(port);
+    let targets: Vec<String> = match id {
+        Some(id) => vec![id.to_string()],
+        None => contexts.clone(),
+    };
+    if targets.is_empty() {
+        print_action(
+            json,
+            "context.dispose",
+            serde_json::json!({ "disposed": [] }),
+            "No contexts to dispose",
+        );
+        return Ok(());
+    }
+
+    let mut cdp = cdp::connect_browser(port).await?;
+    for context_id in &targets {
+        cdp.send(
+            "Target.disposeBrowserContext",
+            serde_json::json!({ "browserContextId": context_id }),
+        )
+        .await?;
+        contexts.retain(|c| c != context_id);
+    }
+    save_contexts(port, &contexts)?;
+    print_action(
+        json,
+        "context.dispose",
+        serde_json::json!({ "disposed": targets }),
+        &format!(
+            "Disposed {} context{}",
+            targets.len(),
+            if targets.len() == 1 { "" } else { "s" }
+        ),
+    );
+    Ok(())
+}
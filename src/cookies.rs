@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+
+use crate::cdp;
+use crate::output::print_action;
+
+/// `Network.Cookie` fields that `Network.setCookies` accepts back as a `CookieParam`
+const COOKIE_PARAM_FIELDS: &[&str] = &[
+    "name",
+    "value",
+    "domain",
+    "path",
+    "secure",
+    "httpOnly",
+    "sameSite",
+    "expires",
+    "priority",
+    "sameParty",
+    "sourceScheme",
+    "sourcePort",
+    "partitionKey",
+];
+
+/// Convert an exported cookie into a `CookieParam`, dropping read-only fields
+/// (`size`, `session`) and the `-1` expiry Chrome reports for session cookies.
+pub(crate) fn cookie_param(cookie: &serde_json::Value) -> serde_json::Value {
+    let mut param = serde_json::Map::new();
+    for field in COOKIE_PARAM_FIELDS {
+        if let Some(value) = cookie.get(*field) {
+            param.insert(field.to_string(), value.clone());
+        }
+    }
+    let session = cookie.get("session").and_then(|s| s.as_bool()) == Some(true);
+    let expires = param.get("expires").and_then(|e| e.as_f64());
+    if session || expires.is_some_and(|e| e < 0.0) {
+        param.remove("expires");
+    }
+    serde_json::Value::Object(param)
+}
+
+/// Read every cookie in the jar of `context` (a `context new` id), or of the
+/// active tab's context when `None`.
+async fn get_cookies(port: u16, context: Option<&str>) -> Result<serde_json::Value> {
+    let result = match context {
+        Some(id) => {
+            cdp::connect_browser(port)
+                .await?
+                .send(
+                    "Storage.getCookies",
+                    serde_json::json!({ "browserContextId": id }),
+                )
+                .await?
+        }
+        None => {
+            cdp::connect_active(port)
+                .await?
+                .send("Network.getAllCookies", serde_json::json!({}))
+                .await?
+        }
+    };
+    Ok(result.get("cookies").cloned().unwrap_or_default())
+}
+
+async fn set_cookies(
+    port: u16,
+    context: Option<&str>,
+    cookies: &[serde_json::Value],
+) -> Result<()> {
+    match context {
+        Some(id) => {
+            cdp::connect_browser(port)
+                .await?
+                .send(
+                    "Storage.setCookies",
+                    serde_json::json!({ "cookies": cookies, "browserContextId": id }),
+                )
+                .await?
+        }
+        None => {
+            cdp::connect_active(port)
+                .await?
+                .send(
+                    "Network.setCookies",
+                    serde_json::json!({ "cookies": cookies }),
+                )
+                .await?
+        }
+    };
+    Ok(())
+}
+
+pub async fn cmd_cookies(
+    port: u16,
+    context: Option<&str>,
+    action: &crate::CookiesCommand,
+    json: bool,
+) -> Result<()> {
+    match action {
+        crate::CookiesCommand::Export { file } => {
+            let cookies = get_cookies(port, context).await?;
+            let count = cookies.as_array().map_or(0, |c| c.len());
+            let file = &crate::output::prepare_output_path(file)?;
+            std::fs::write(file, serde_json::to_string_pretty(&cookies)?)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            print_action(
+                json,
+                "cookies.export",
+                serde_json::json!({ "file": file, "count": count, "contextId": context }),
+                &format!("Exported {} cookies to {}", count, file.display()),
+            );
+        }
+        crate::CookiesCommand::Import { file } => {
+            let data = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let cookies: Vec<serde_json::Value> = serde_json::from_str(&data)
+                .with_context(|| format!("{} is not a JSON array of cookies", file.display()))?;
+            let params: Vec<serde_json::Value> = cookies.iter().map(cookie_param).collect();
+            set_cookies(port, context, &params).await?;
+            print_action(
+                json,
+                "cookies.import",
+                serde_json::json!({ "file": file, "count": params.len(), "contextId": context }),
+                &format!("Imported {} cookies from {}", params.len(), file.display()),
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cookie_param;
+
+    #[test]
+    fn cookie_param_keeps_settable_fields() {
+        let cookie = serde_json::json!({
+            "name": "sid",
+            "value": "abc",
+            "domain": ".example.com",
+            "path": "/",
+            "expires": 1900000000.5,
+            "size": 6,
+            "httpOnly": true,
+            "secure": true,
+            "session": false,
+            "sameSite": "Lax",
+            "priority": "Medium",
+        });
+        let param = cookie_param(&cookie);
+
+        assert_eq!(param["expires"], 1900000000.5);
+        assert_eq!(param["sameSite"], "Lax");
+        assert!(param.get("size").is_none());
+        assert!(param.get("session").is_none());
+    }
+
+    #[test]
+    fn cookie_param_drops_session_expiry() {
+        let cookie = serde_json::json!({
+            "name": "tmp",
+            "value": "1",
+            "domain": "example.com",
+            "expires": -1,
+            "session": true,
+        });
+
+        assert!(cookie_param(&cookie).get("expires").is_none());
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio::time::{Duration, Instant};
+
+use crate::cdp;
+use crate::errors::ErrorKind;
+use crate::output::print_action;
+
+/// Pause JavaScript on the active page so the DOM can be inspected while it holds
+/// still. The pause lasts while this command runs: Chrome resumes when the
+/// debugging session detaches, so it ends after `duration_ms`, on Ctrl-C, or when
+/// `resume` is run from another shell.
+pub async fn cmd_freeze(port: u16, duration_ms: Option<u64>, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send("Debugger.enable", serde_json::json!({})).await?;
+    cdp.send("Debugger.pause", serde_json::json!({})).await?;
+    print_action(
+        json,
+        "freeze",
+        serde_json::json!({ "durationMs": duration_ms }),
+        "Page frozen (run `resume` or press Ctrl-C to continue)",
+    );
+
+    let deadline = duration_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+    loop {
+        let sleep_until = deadline.unwrap_or_else(|| Instant::now() + Duration::from_secs(86400));
+        let message = tokio::select! {
+            message = cdp.recv() => message?.context(ErrorKind::Connection.error("Connection closed while frozen"))?,
+            _ = tokio::time::sleep_until(sleep_until) => break,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        if message.get("method").and_then(Value::as_str) == Some("Debugger.resumed") {
+            return Ok(());
+        }
+    }
+    // Detaching resumes too, so a failure here (e.g. the page navigated) is harmless.
+    let _ = cdp.send("Debugger.resume", serde_json::json!({})).await;
+    Ok(())
+}
+
+pub async fn cmd_resume(port: u16, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send("Debugger.enable", serde_json::json!({})).await?;
+    cdp.send("Debugger.resume", serde_json::json!({})).await?;
+    print_action(json, "resume", serde_json::json!({}), "Page resumed");
+    Ok(())
+}
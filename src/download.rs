@@ -0,0 +1,179 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::time::{Duration, Instant, timeout};
+
+use crate::cdp;
+use crate::commands::click_element;
+use crate::errors::ErrorKind;
+use crate::output::print_action;
+
+/// The download a click started, as announced by `downloadWillBegin`
+#[derive(Debug, PartialEq)]
+pub(crate) struct DownloadStart {
+    pub guid: String,
+    pub filename: String,
+}
+
+pub(crate) fn download_start(params: &Value) -> Option<DownloadStart> {
+    Some(DownloadStart {
+        guid: params.get("guid")?.as_str()?.to_string(),
+        filename: params
+            .get("suggestedFilename")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+/// Where to keep a download saved under its guid: the suggested name without
+/// any directory part, numbered `name (1).ext`, `name (2).ext`, ... when a file
+/// of that name is already there.
+pub(crate) fn unique_download_path(dir: &Path, suggested: &str) -> Result<PathBuf> {
+    let name = Path::new(suggested)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.is_empty())
+        .with_context(|| format!("Download has no usable file name ({:?})", suggested))?;
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return Ok(candidate);
+    }
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .context("No free file name for the download")
+}
+
+/// Click `selector` with downloads routed to `dir`, then wait for the download to finish.
+pub async fn cmd_download(
+    port: u16,
+    selector: &str,
+    dir: &Path,
+    timeout_ms: u64,
+    json: bool,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create download directory {}", dir.display()))?;
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", dir.display()))?;
+
+    // Chrome saves the file under its guid and we pick the final name, so the
+    // reported path is the file this download wrote even when Chrome would have
+    // renamed it to avoid an existing one. Events are enabled before the click,
+    // and any that arrive while the click is evaluated are queued for `recv`.
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send(
+        "Browser.setDownloadBehavior",
+        serde_json::json!({
+            "behavior": "allowAndName",
+            "downloadPath": dir,
+            "eventsEnabled": true,
+        }),
+    )
+    .await?;
+    click_element(&mut cdp, selector, None).await?;
+
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut started: Option<DownloadStart> = None;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let message = match timeout(remaining, cdp.recv()).await {
+            Ok(message) => message?.context(
+                ErrorKind::Connection.error("Connection closed while waiting for download"),
+            )?,
+            Err(_) if started.is_none() => {
+                return Err(ErrorKind::Timeout
+                    .error(format!("No download started within {}ms", timeout_ms))
+                    .into());
+            }
+            Err(_) => {
+                return Err(ErrorKind::Timeout
+                    .error(format!("Download did not finish within {}ms", timeout_ms))
+                    .into());
+            }
+        };
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Value::Null);
+        match method {
+            "Browser.downloadWillBegin" if started.is_none() => {
+                started = download_start(params);
+            }
+            "Browser.downloadProgress" => {
+                let Some(start) = &started else { continue };
+                if params.get("guid").and_then(Value::as_str) != Some(start.guid.as_str()) {
+                    continue;
+                }
+                match params.get("state").and_then(Value::as_str) {
+                    Some("completed") => break,
+                    Some("canceled") => return Err(anyhow!("Download was canceled")),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let start = started.context("Download finished without a downloadWillBegin event")?;
+    let saved = dir.join(&start.guid);
+    let path = unique_download_path(&dir, &start.filename)?;
+    std::fs::rename(&saved, &path)
+        .with_context(|| format!("Failed to move {} to {}", saved.display(), path.display()))?;
+    print_action(
+        json,
+        "download",
+        serde_json::json!({ "selector": selector, "path": path }),
+        &format!("Downloaded {}", path.display()),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DownloadStart, download_start, unique_download_path};
+    use std::path::Path;
+
+    #[test]
+    fn download_start_needs_a_guid() {
+        let params = serde_json::json!({ "guid": "abc", "suggestedFilename": "report.pdf" });
+        assert_eq!(
+            download_start(&params),
+            Some(DownloadStart {
+                guid: "abc".to_string(),
+                filename: "report.pdf".to_string(),
+            })
+        );
+        assert_eq!(
+            download_start(&serde_json::json!({ "suggestedFilename": "x" })),
+            None
+        );
+    }
+
+    #[test]
+    fn unique_download_path_numbers_collisions_and_strips_directories() {
+        let dir = std::env::temp_dir().join(format!("browser-cli-download-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("report.pdf"), b"old").unwrap();
+        std::fs::write(dir.join("report (1).pdf"), b"older").unwrap();
+
+        let fresh = unique_download_path(&dir, "notes.txt").unwrap();
+        let renamed = unique_download_path(&dir, "report.pdf").unwrap();
+        let stripped = unique_download_path(&dir, "../../etc/passwd").unwrap();
+        let empty = unique_download_path(&dir, "");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(fresh, dir.join("notes.txt"));
+        assert_eq!(renamed, dir.join("report (2).pdf"));
+        assert_eq!(stripped.parent(), Some(dir.as_path()));
+        assert_eq!(stripped.file_name(), Some(Path::new("passwd").as_os_str()));
+        assert!(empty.is_err());
+    }
+}
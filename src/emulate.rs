@@ -0,0 +1,220 @@
+use anyhow::{Result, anyhow};
+
+use crate::cdp::{self, CdpConnection};
+use crate::output::print_action;
+
+/// Viewport size for the global `--viewport` flag
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    pub mobile: bool,
+}
+
+/// Named sizes accepted by `--viewport` as (name, width, height, mobile)
+const VIEWPORT_PRESETS: &[(&str, u32, u32, bool)] = &[
+    ("desktop", 1440, 900, false),
+    ("laptop", 1280, 720, false),
+    ("tablet", 768, 1024, true),
+    ("mobile", 390, 844, true),
+];
+
+/// Parse `WIDTHxHEIGHT` or a preset name.
+pub fn parse_viewport(value: &str) -> Result<Viewport> {
+    if let Some(&(_, width, height, mobile)) = VIEWPORT_PRESETS
+        .iter()
+        .find(|(name, ..)| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(Viewport {
+            width,
+            height,
+            mobile,
+        });
+    }
+    let (width, height) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| anyhow!("expected WIDTHxHEIGHT or desktop, laptop, tablet, mobile"))?;
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| anyhow!("invalid viewport dimension '{}'", n))
+    };
+    Ok(Viewport {
+        width: parse(width)?,
+        height: parse(height)?,
+        mobile: false,
+    })
+}
+
+/// Apply a viewport override on a dedicated session. Overrides last only while
+/// the session is attached, so the caller keeps the connection open for the
+/// duration of the command and passes it to `release_viewport` afterwards.
+pub async fn hold_viewport(port: u16, viewport: Viewport) -> Result<CdpConnection> {
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send(
+        "Emulation.setDeviceMetricsOverride",
+        serde_json::json!({
+            "width": viewport.width,
+            "height": viewport.height,
+            "deviceScaleFactor": 0,
+            "mobile": viewport.mobile,
+        }),
+    )
+    .await?;
+    Ok(cdp)
+}
+
+pub async fn release_viewport(mut cdp: CdpConnection) -> Result<()> {
+    cdp.send(
+        "Emulation.clearDeviceMetricsOverride",
+        serde_json::json!({}),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn cmd_emulate(port: u16, action: &crate::EmulateCommand, json: bool) -> Result<()> {
+    match action {
+        crate::EmulateCommand::Cpu { rate, reset, hold } => {
+            let rate = if *reset { 1.0 } else { rate.unwrap_or(1.0) };
+            set_cpu_throttling(port, rate, *hold, json).await
+        }
+    }
+}
+
+async fn set_cpu_throttling(port: u16, rate: f64, hold: Option<u64>, json: bool) -> Result<()> {
+    if rate < 1.0 {
+        return Err(anyhow!("--rate must be 1 (no throttle) or higher"));
+    }
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send(
+        "Emulation.setCPUThrottlingRate",
+        serde_json::json!({ "rate": rate }),
+    )
+    .await?;
+
+    let message = match (rate == 1.0, hold) {
+        (true, _) => "CPU throttling cleared".to_string(),
+        (false, Some(ms)) => format!("CPU throttled {}x, holding session for {}ms", rate, ms),
+        (false, None) => format!(
+            "CPU throttled {}x (session-scoped: cleared when this command exits, use --hold)",
+            rate
+        ),
+    };
+    print_action(
+        json,
+        "emulate.cpu",
+        serde_json::json!({ "rate": rate, "sessionScoped": true, "holdMs": hold }),
+        &message,
+    );
+    if let Some(ms) = hold {
+        tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+    }
+    Ok(())
+}
+
+/// Zoom range Chrome itself offers through Ctrl+Plus/Ctrl+Minus
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 5.0;
+
+pub(crate) fn clamp_zoom(factor: f64) -> Result<f64> {
+    if !factor.is_finite() || factor <= 0.0 {
+        return Err(anyhow!("zoom factor must be a positive number"));
+    }
+    Ok(factor.clamp(MIN_ZOOM, MAX_ZOOM))
+}
+
+/// Zoom the way Ctrl+Plus does: the CSS viewport shrinks by `factor`, pixels get
+/// denser by the same amount, and the rendered view is scaled back up to fill
+/// the window. `None` clears the override.
+pub async fn cmd_zoom(port: u16, factor: Option<f64>, hold: Option<u64>, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let Some(requested) = factor else {
+        cdp.send(
+            "Emulation.clearDeviceMetricsOverride",
+            serde_json::json!({}),
+        )
+        .await?;
+        print_action(
+            json,
+            "zoom",
+            serde_json::json!({ "factor": 1.0, "sessionScoped": true }),
+            "Zoom reset to 100%",
+        );
+        return Ok(());
+    };
+    let factor = clamp_zoom(requested)?;
+    if factor != requested {
+        eprintln!("note: zoom factor clamped to {}", factor);
+    }
+    let window = cdp
+        .eval("({ width: innerWidth, height: innerHeight, dpr: devicePixelRatio })")
+        .await?;
+    let dimension = |key: &str| window.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let (width, height, dpr) = (dimension("width"), dimension("height"), dimension("dpr"));
+    if width <= 0.0 || height <= 0.0 {
+        return Err(anyhow!("Could not read the window size"));
+    }
+    cdp.send(
+        "Emulation.setDeviceMetricsOverride",
+        serde_json::json!({
+            "width": (width / factor).round() as u32,
+            "height": (height / factor).round() as u32,
+            "deviceScaleFactor": dpr.max(1.0) * factor,
+            "mobile": false,
+            "scale": factor,
+        }),
+    )
+    .await?;
+
+    let percent = (factor * 100.0).round();
+    let message = match hold {
+        Some(ms) => format!("Zoomed to {}%, holding session for {}ms", percent, ms),
+        None => format!(
+            "Zoomed to {}% (session-scoped: cleared when this command exits, use --hold)",
+            percent
+        ),
+    };
+    print_action(
+        json,
+        "zoom",
+        serde_json::json!({ "factor": factor, "sessionScoped": true, "holdMs": hold }),
+        &message,
+    );
+    if let Some(ms) = hold {
+        tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Viewport, clamp_zoom, parse_viewport};
+
+    #[test]
+    fn clamps_zoom_to_browser_range() {
+        assert_eq!(clamp_zoom(1.5).unwrap(), 1.5);
+        assert_eq!(clamp_zoom(0.1).unwrap(), 0.25);
+        assert_eq!(clamp_zoom(12.0).unwrap(), 5.0);
+        assert!(clamp_zoom(0.0).is_err());
+        assert!(clamp_zoom(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn parses_dimensions_and_presets() {
+        assert_eq!(
+            parse_viewport("1280x720").unwrap(),
+            Viewport {
+                width: 1280,
+                height: 720,
+                mobile: false
+            }
+        );
+        assert!(parse_viewport("Mobile").unwrap().mobile);
+        assert_eq!(parse_viewport("tablet").unwrap().width, 768);
+        assert!(parse_viewport("1280").is_err());
+        assert!(parse_viewport("0x720").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use serde_json::Value;
+
+/// Broad failure categories, each with its own exit code so scripts can tell
+/// "element not found" apart from "Chrome not running".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorKind {
+    Other,
+    Connection,
+    TargetNotFound,
+    ElementNotFound,
+    Timeout,
+    Protocol,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Connection => 2,
+            ErrorKind::TargetNotFound => 3,
+            ErrorKind::ElementNotFound => 4,
+            ErrorKind::Timeout => 5,
+            ErrorKind::Protocol => 6,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Other => "other",
+            ErrorKind::Connection => "connection",
+            ErrorKind::TargetNotFound => "target_not_found",
+            ErrorKind::ElementNotFound => "element_not_found",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Protocol => "protocol",
+        }
+    }
+
+    /// An error of this kind, for code that knows the category of what failed.
+    /// Usable directly (`bail!`) or as `.context`.
+    pub fn error(self, message: impl std::fmt::Display) -> Categorized {
+        Categorized {
+            kind: self,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// An error tagged with its `ErrorKind` where it was raised. Displays as just
+/// its message.
+#[derive(Debug)]
+pub struct Categorized {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl std::fmt::Display for Categorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Categorized {}
+
+/// Use the kind tagged where the error was raised, then the error type; only
+/// untagged plain messages (e.g. exceptions thrown by injected JS) are
+/// classified by their text.
+pub fn classify(err: &anyhow::Error) -> ErrorKind {
+    if let Some(categorized) = err.downcast_ref::<Categorized>() {
+        return categorized.kind;
+    }
+    for cause in err.chain() {
+        if cause.is::<crate::cdp::ConnectionClosed>()
+            || cause.is::<tokio_tungstenite::tungstenite::Error>()
+        {
+            return ErrorKind::Connection;
+        }
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return ErrorKind::Timeout;
+        }
+    }
+    err.chain()
+        .map(|cause| classify_message(&cause.to_string()))
+        .find(|kind| *kind != ErrorKind::Other)
+        .unwrap_or(ErrorKind::Other)
+}
+
+pub(crate) fn classify_message(message: &str) -> ErrorKind {
+    let message = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+    if has(&[
+        "element not found",
+        "ref not found",
+        "no element",
+        "no node",
+    ]) {
+        ErrorKind::ElementNotFound
+    } else if has(&[
+        "failed to connect to chrome",
+        "connection closed while",
+        "no response from cdp",
+    ]) {
+        ErrorKind::Connection
+    } else if is_timeout(&message) {
+        ErrorKind::Timeout
+    } else if has(&[
+        "no pages found",
+        "tab index",
+        "no browser targets",
+        "no target",
+    ]) {
+        ErrorKind::TargetNotFound
+    } else if has(&["cdp error"]) {
+        ErrorKind::Protocol
+    } else {
+        ErrorKind::Other
+    }
+}
+
+/// The crate's own timeouts, including those rejected by injected scripts,
+/// all read "Timeout: ...". Page errors that merely mention a timeout don't.
+fn is_timeout(message: &str) -> bool {
+    let message = message.trim_start_matches("uncaught ");
+    let message = message.strip_prefix("error: ").unwrap_or(message);
+    message.starts_with("timeout:")
+}
+
+pub(crate) fn error_json(err: &anyhow::Error, kind: ErrorKind) -> Value {
+    serde_json::json!({
+        "ok": false,
+        "error": { "kind": kind.as_str(), "message": format!("{:#}", err) },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorKind, classify, classify_message, error_json};
+
+    #[test]
+    fn classifies_common_failures() {
+        assert_eq!(
+            classify_message("Error: Element not found: #go"),
+            ErrorKind::ElementNotFound
+        );
+        assert_eq!(
+            classify_message("Error: Timeout: page kept changing"),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            classify_message("Tab index out of range"),
+            ErrorKind::TargetNotFound
+        );
+        assert_eq!(
+            classify_message(r#"CDP error: {"code":-32601,"message":"'Foo.bar' wasn't found"}"#),
+            ErrorKind::Protocol
+        );
+        assert_eq!(
+            classify_message("--dsf must be between 1 and 4"),
+            ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn typed_errors_take_precedence_over_message() {
+        let err = anyhow::Error::new(crate::cdp::ConnectionClosed).context("Element not found");
+        assert_eq!(classify(&err), ErrorKind::Connection);
+    }
+
+    #[test]
+    fn tagged_kind_is_used_through_context() {
+        let err = anyhow::Error::new(ErrorKind::Connection.error("Chrome not found in PATH"))
+            .context("While opening https://example.com");
+        assert_eq!(classify(&err), ErrorKind::Connection);
+        assert_eq!(
+            format!("{:#}", err),
+            "While opening https://example.com: Chrome not found in PATH"
+        );
+
+        let err = anyhow::Error::new(ErrorKind::Timeout.error("No download started within 5000ms"));
+        assert_eq!(classify(&err), ErrorKind::Timeout);
+        let err =
+            anyhow::Error::new(ErrorKind::Timeout.error("Download did not finish within 5000ms"));
+        assert_eq!(classify(&err), ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn page_urls_and_site_fetches_are_not_connection_errors() {
+        assert_eq!(
+            classify_message("Navigation failed: net::ERR_CONNECTION_TIMED_OUT"),
+            ErrorKind::Other
+        );
+
+        let fetch = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let err = anyhow::Error::new(fetch).context("Failed to fetch not a url");
+        assert_eq!(classify(&err), ErrorKind::Other);
+    }
+
+    #[test]
+    fn page_exceptions_are_not_classified_by_their_words() {
+        // Descriptions as `cdp::exception_message` reports them for
+        // `eval "throw new Error('connection lost')"` and friends.
+        assert_eq!(
+            classify_message("Error: connection lost\n    at <anonymous>:1:7"),
+            ErrorKind::Other
+        );
+        assert_eq!(
+            classify_message("Error: Request timeout after 3 retries\n    at <anonymous>:1:7"),
+            ErrorKind::Other
+        );
+        assert_eq!(
+            classify_message("Uncaught TypeError: Failed to fetch"),
+            ErrorKind::Other
+        );
+        assert_eq!(
+            classify_message(
+                "Error: Timeout: none of the conditions matched\n    at <anonymous>:9:43"
+            ),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            classify_message("No response from CDP (connection closed)"),
+            ErrorKind::Connection
+        );
+    }
+
+    #[test]
+    fn error_json_nests_kind_and_message() {
+        let err = anyhow::anyhow!("Element not found");
+        let out = error_json(&err, ErrorKind::ElementNotFound);
+        assert_eq!(out["ok"], false);
+        assert_eq!(out["error"]["kind"], "element_not_found");
+        assert_eq!(out["error"]["message"], "Element not found");
+    }
+}
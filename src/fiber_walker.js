@@ -82,6 +82,8 @@
       return { x: minX, y: minY, width: Math.max(0, maxX - minX), height: Math.max(0, maxY - minY) };
     }
 
+    // Fragments and anonymous components have no name here, so processOneFiber
+    // promotes their children in place instead of emitting a node.
     function getComponentName(fiber) {
       if (!fiber.type) return null;
       if (typeof fiber.type === 'string') return null;
@@ -225,7 +227,20 @@
       return childNodes;
     }
 
-    const tree = processFiber(root.child, 0);
+    let tree;
+    const rootSelector = globalThis.__ROOT_SELECTOR;
+    if (rootSelector) {
+      let el = globalThis.__ROOT_ELEMENT;
+      if (!el) throw new Error('Root element not found: ' + rootSelector);
+      let key = null;
+      while (el && !(key = Object.keys(el).find(k => k.startsWith('__reactFiber$') || k.startsWith('__reactInternalInstance$')))) {
+        el = el.parentElement;
+      }
+      if (!el) return { found: false, tree: [], allMinified: false };
+      tree = processOneFiber(el[key], 0);
+    } else {
+      tree = processFiber(root.child, 0);
+    }
     const allMinified = totalComponents > 0 && minifiedComponents === totalComponents;
     return { found: true, tree, allMinified };
   })()
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+
+use crate::cdp;
+use crate::commands::element_expr;
+use crate::output::print_action;
+
+const DROP_SCRIPT_TEMPLATE: &str = r#"(() => {
+    const el = __ELEMENT__;
+    if (!el) throw new Error('Element not found');
+    const dataTransfer = new DataTransfer();
+    for (const file of __FILES__) {
+        const binary = atob(file.data);
+        const bytes = new Uint8Array(binary.length);
+        for (let i = 0; i < binary.length; i++) bytes[i] = binary.charCodeAt(i);
+        dataTransfer.items.add(new File([bytes], file.name, { type: file.type }));
+    }
+    el.scrollIntoView({ block: 'center' });
+    const rect = el.getBoundingClientRect();
+    const clientX = rect.left + rect.width / 2;
+    const clientY = rect.top + rect.height / 2;
+    for (const type of ['dragenter', 'dragover', 'drop']) {
+        el.dispatchEvent(new DragEvent(type, {
+            bubbles: true, cancelable: true, composed: true, dataTransfer, clientX, clientY,
+        }));
+    }
+    return dataTransfer.files.length;
+})()"#;
+
+/// MIME types for common upload extensions; anything else is sent untyped.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("pdf", "application/pdf"),
+    ("json", "application/json"),
+    ("csv", "text/csv"),
+    ("txt", "text/plain"),
+    ("html", "text/html"),
+    ("zip", "application/zip"),
+];
+
+pub(crate) fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map_or("", |(_, mime)| mime)
+}
+
+/// Simulate dropping files from disk onto `selector`. Pages cannot read local
+/// paths, so the bytes are read here and rebuilt as `File` objects in the page.
+pub async fn cmd_drag_file(
+    port: u16,
+    selector: &str,
+    nth: Option<usize>,
+    files: &[String],
+    json: bool,
+) -> Result<()> {
+    if files.is_empty() {
+        return Err(anyhow!("At least one file path is required"));
+    }
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut payload = Vec::new();
+    for file in files {
+        let path = Path::new(file);
+        let bytes = std::fs::read(path).with_context(|| format!("File not found: {}", file))?;
+        payload.push(serde_json::json!({
+            "name": path.file_name().and_then(|n| n.to_str()).unwrap_or(file),
+            "type": mime_type(path),
+            "data": engine.encode(bytes),
+        }));
+    }
+    let script = DROP_SCRIPT_TEMPLATE
+        .replace("__ELEMENT__", &element_expr(selector, nth)?)
+        .replace("__FILES__", &serde_json::to_string(&payload)?);
+
+    let mut cdp = cdp::connect_active(port).await?;
+    crate::commands::await_element(&mut cdp, selector, nth).await?;
+    cdp.eval(&script).await?;
+    print_action(
+        json,
+        "drag-file",
+        serde_json::json!({ "selector": selector, "files": files }),
+        &format!(
+            "Dropped {} file{} on {}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            selector
+        ),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mime_type;
+    use std::path::Path;
+
+    #[test]
+    fn mime_type_by_extension() {
+        assert_eq!(mime_type(Path::new("/tmp/photo.JPG")), "image/jpeg");
+        assert_eq!(mime_type(Path::new("report.pdf")), "application/pdf");
+        assert_eq!(mime_type(Path::new("Makefile")), "");
+    }
+}
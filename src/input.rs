@@ -0,0 +1,266 @@
+use anyhow::Result;
+
+use crate::cdp::{self, CdpConnection};
+use crate::output::print_action;
+
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    fn cdp_name(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Middle => "middle",
+            Self::Right => "right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Modifier {
+    Alt,
+    Ctrl,
+    Meta,
+    Shift,
+}
+
+/// CDP `modifiers` bitmask: Alt=1, Ctrl=2, Meta=4, Shift=8.
+pub(crate) fn modifier_mask(modifiers: &[Modifier]) -> i64 {
+    modifiers.iter().fold(0, |mask, m| {
+        mask | match m {
+            Modifier::Alt => 1,
+            Modifier::Ctrl => 2,
+            Modifier::Meta => 4,
+            Modifier::Shift => 8,
+        }
+    })
+}
+
+/// `Input.dispatchKeyEvent` fields for a single key
+#[derive(Debug, PartialEq)]
+pub(crate) struct KeyDefinition {
+    pub key: String,
+    pub code: String,
+    pub key_code: u32,
+    pub text: Option<String>,
+}
+
+/// Named keys as (key, code, windowsVirtualKeyCode, text).
+const NAMED_KEYS: &[(&str, &str, u32, Option<&str>)] = &[
+    ("Enter", "Enter", 13, Some("\r")),
+    ("Tab", "Tab", 9, None),
+    ("Escape", "Escape", 27, None),
+    ("Backspace", "Backspace", 8, None),
+    ("Delete", "Delete", 46, None),
+    ("Insert", "Insert", 45, None),
+    ("Home", "Home", 36, None),
+    ("End", "End", 35, None),
+    ("PageUp", "PageUp", 33, None),
+    ("PageDown", "PageDown", 34, None),
+    ("ArrowLeft", "ArrowLeft", 37, None),
+    ("ArrowUp", "ArrowUp", 38, None),
+    ("ArrowRight", "ArrowRight", 39, None),
+    ("ArrowDown", "ArrowDown", 40, None),
+    ("Shift", "ShiftLeft", 16, None),
+    ("Control", "ControlLeft", 17, None),
+    ("Alt", "AltLeft", 18, None),
+    ("Meta", "MetaLeft", 91, None),
+    ("CapsLock", "CapsLock", 20, None),
+    (" ", "Space", 32, Some(" ")),
+];
+
+/// Punctuation on a US layout as (key, code, windowsVirtualKeyCode).
+const PUNCTUATION_KEYS: &[(char, &str, u32)] = &[
+    (';', "Semicolon", 186),
+    ('=', "Equal", 187),
+    (',', "Comma", 188),
+    ('-', "Minus", 189),
+    ('.', "Period", 190),
+    ('/', "Slash", 191),
+    ('`', "Backquote", 192),
+    ('[', "BracketLeft", 219),
+    ('\\', "Backslash", 220),
+    (']', "BracketRight", 221),
+    ('\'', "Quote", 222),
+];
+
+/// Resolve a key name (`Enter`, `ArrowDown`, `a`, `F5`, `Space`) to the fields
+/// real keyboards report, so pages reading `event.code`/`keyCode` respond.
+pub(crate) fn key_definition(name: &str) -> KeyDefinition {
+    let name = match name {
+        "Space" => " ",
+        "Esc" => "Escape",
+        "Return" => "Enter",
+        "Up" | "Down" | "Left" | "Right" => return key_definition(&format!("Arrow{}", name)),
+        other => other,
+    };
+    let def = |key: &str, code: &str, key_code: u32, text: Option<&str>| KeyDefinition {
+        key: key.to_string(),
+        code: code.to_string(),
+        key_code,
+        text: text.map(str::to_string),
+    };
+
+    if let Some(&(key, code, key_code, text)) = NAMED_KEYS.iter().find(|k| k.0 == name) {
+        return def(key, code, key_code, text);
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u32>().ok())
+        && (1..=12).contains(&n)
+    {
+        return def(name, name, 111 + n, None);
+    }
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => {
+            let upper = c.to_ascii_uppercase();
+            def(name, &format!("Key{}", upper), upper as u32, Some(name))
+        }
+        (Some(c), None) if c.is_ascii_digit() => {
+            def(name, &format!("Digit{}", c), c as u32, Some(name))
+        }
+        (Some(c), None) => match PUNCTUATION_KEYS.iter().find(|k| k.0 == c) {
+            Some(&(_, code, key_code)) => def(name, code, key_code, Some(name)),
+            None => def(name, "", 0, Some(name)),
+        },
+        _ => def(name, "", 0, None),
+    }
+}
+
+/// Press and release a key: `rawKeyDown`, `char` (printable keys only), `keyUp`.
+pub(crate) async fn press_key(cdp: &mut CdpConnection, name: &str) -> Result<()> {
+    let key = key_definition(name);
+    let event = |kind: &str| {
+        serde_json::json!({
+            "type": kind,
+            "key": key.key,
+            "code": key.code,
+            "windowsVirtualKeyCode": key.key_code,
+            "nativeVirtualKeyCode": key.key_code,
+        })
+    };
+    cdp.send("Input.dispatchKeyEvent", event("rawKeyDown"))
+        .await?;
+    if let Some(text) = &key.text {
+        let mut char_event = event("char");
+        char_event["text"] = serde_json::json!(text);
+        char_event["unmodifiedText"] = serde_json::json!(text);
+        cdp.send("Input.dispatchKeyEvent", char_event).await?;
+    }
+    cdp.send("Input.dispatchKeyEvent", event("keyUp")).await?;
+    Ok(())
+}
+
+/// Viewport coordinates plus button/modifier state shared by mouse subcommands
+#[derive(clap::Args, Clone)]
+pub struct MouseArgs {
+    pub x: f64,
+    pub y: f64,
+    /// Mouse button
+    #[arg(long, value_enum, default_value_t)]
+    pub button: MouseButton,
+    /// Held modifier keys (comma-separated: alt,ctrl,meta,shift)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub modifiers: Vec<Modifier>,
+}
+
+async fn dispatch_mouse(
+    cdp: &mut CdpConnection,
+    kind: &str,
+    args: &MouseArgs,
+    click_count: u32,
+) -> Result<()> {
+    let button = if kind == "mouseMoved" {
+        "none"
+    } else {
+        args.button.cdp_name()
+    };
+    cdp.send(
+        "Input.dispatchMouseEvent",
+        serde_json::json!({
+            "type": kind,
+            "x": args.x,
+            "y": args.y,
+            "button": button,
+            "clickCount": click_count,
+            "modifiers": modifier_mask(&args.modifiers),
+        }),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn cmd_mouse(port: u16, action: &crate::MouseCommand, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let (name, args) = match action {
+        crate::MouseCommand::Move(args) => {
+            dispatch_mouse(&mut cdp, "mouseMoved", args, 0).await?;
+            ("move", args)
+        }
+        crate::MouseCommand::Click(args) => {
+            dispatch_mouse(&mut cdp, "mouseMoved", args, 0).await?;
+            dispatch_mouse(&mut cdp, "mousePressed", args, 1).await?;
+            dispatch_mouse(&mut cdp, "mouseReleased", args, 1).await?;
+            ("click", args)
+        }
+        crate::MouseCommand::Down(args) => {
+            dispatch_mouse(&mut cdp, "mousePressed", args, 1).await?;
+            ("down", args)
+        }
+        crate::MouseCommand::Up(args) => {
+            dispatch_mouse(&mut cdp, "mouseReleased", args, 1).await?;
+            ("up", args)
+        }
+    };
+    print_action(
+        json,
+        &format!("mouse.{}", name),
+        serde_json::json!({ "x": args.x, "y": args.y, "button": args.button.cdp_name() }),
+        &format!("Mouse {} at {},{}", name, args.x, args.y),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyDefinition, Modifier, key_definition, modifier_mask};
+
+    fn key(key: &str, code: &str, key_code: u32, text: Option<&str>) -> KeyDefinition {
+        KeyDefinition {
+            key: key.to_string(),
+            code: code.to_string(),
+            key_code,
+            text: text.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn key_definition_fills_code_and_key_code() {
+        assert_eq!(
+            key_definition("Enter"),
+            key("Enter", "Enter", 13, Some("\r"))
+        );
+        assert_eq!(
+            key_definition("ArrowDown"),
+            key("ArrowDown", "ArrowDown", 40, None)
+        );
+        assert_eq!(key_definition("a"), key("a", "KeyA", 65, Some("a")));
+        assert_eq!(key_definition("Z"), key("Z", "KeyZ", 90, Some("Z")));
+        assert_eq!(key_definition("7"), key("7", "Digit7", 55, Some("7")));
+        assert_eq!(key_definition("Space"), key(" ", "Space", 32, Some(" ")));
+        assert_eq!(key_definition("F5"), key("F5", "F5", 116, None));
+        assert_eq!(key_definition("/"), key("/", "Slash", 191, Some("/")));
+    }
+
+    #[test]
+    fn modifier_mask_combines_bits() {
+        assert_eq!(modifier_mask(&[]), 0);
+        assert_eq!(modifier_mask(&[Modifier::Ctrl, Modifier::Shift]), 10);
+        assert_eq!(modifier_mask(&[Modifier::Alt, Modifier::Meta]), 5);
+    }
+}
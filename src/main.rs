@@ -1,12 +1,32 @@
 mod cdp;
 mod commands;
+mod context;
+mod cookies;
+mod debugger;
+mod download;
+mod emulate;
+mod errors;
+mod file_drop;
+mod input;
+mod navigation;
+mod network;
+mod output;
+mod permissions;
+mod record;
+mod refs;
 mod runtime;
+mod screenshot_diff;
+mod scroll;
+mod selector;
 mod snapshot;
 #[cfg(test)]
 mod snapshot_tests;
+mod source;
+mod touch;
+mod transcript;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 
 const DEFAULT_CDP_PORT: u16 = 9222;
 
@@ -22,39 +42,252 @@ struct Cli {
     #[arg(long)]
     json: bool,
 
+    /// Print each CDP method the command sends with its raw result, as JSON lines on stderr
+    #[arg(long)]
+    dump_protocol: bool,
+
+    /// Append each invocation (command, args, timing, outcome) to this JSON Lines file
+    #[arg(long, value_name = "PATH")]
+    transcript: Option<std::path::PathBuf>,
+
+    /// Output format; ndjson prints list results one JSON value per line
+    #[arg(long, value_enum, default_value_t)]
+    format: output::OutputFormat,
+
+    /// Milliseconds to wait for a newly launched Chrome to accept connections
+    #[arg(long, default_value_t = 5000)]
+    launch_timeout: u64,
+
+    /// Retry the Chrome target list request up to N times on transient HTTP errors
+    #[arg(long, default_value_t = 2)]
+    connect_retry: u32,
+
+    /// Milliseconds actions wait for a missing element to appear (0: fail at once)
+    #[arg(long, default_value_t = 1000, value_name = "MS")]
+    select_timeout: u64,
+
+    /// Re-run the command up to N more times if it fails
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Milliseconds to wait between retries
+    #[arg(long, default_value_t = 1000, requires = "retries")]
+    retry_delay: u64,
+
+    /// Run page commands against this target id (from `tabs list --json`)
+    #[arg(long)]
+    target_id: Option<String>,
+
+    /// How to interpret selectors (auto: xpath=/text=/css= prefixes, // for XPath)
+    #[arg(long, value_enum, default_value_t)]
+    selector_engine: selector::SelectorEngine,
+
+    /// Launch Chrome with --no-sandbox (automatic when running as root or in Docker)
+    #[arg(long)]
+    no_sandbox: bool,
+
+    /// Accept invalid TLS certificates (self-signed staging hosts). Insecure
+    #[arg(long)]
+    ignore_https_errors: bool,
+
+    /// Launch Chrome with a persistent profile in ~/.cache/browser-cli/profiles/<NAME>
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Ignore the page's Content-Security-Policy while the command runs
+    #[arg(long)]
+    bypass_csp: bool,
+
+    /// Viewport for this command: WIDTHxHEIGHT or desktop, laptop, tablet, mobile
+    #[arg(long, value_parser = parse_viewport_arg)]
+    viewport: Option<emulate::Viewport>,
+
+    /// Milliseconds to sleep after an input command (click, fill, type, press, ...) succeeds
+    #[arg(long, default_value_t = 0)]
+    wait_after: u64,
+
     #[command(subcommand)]
     command: Command,
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 enum Command {
     /// Navigate to a URL
     #[command(visible_alias = "goto", visible_alias = "navigate")]
-    Open { url: String },
-    /// Go back in history
-    Back,
-    /// Go forward in history
-    Forward,
-    /// Reload current page
-    Reload,
-    /// Close browser/tab
-    #[command(visible_alias = "quit", visible_alias = "exit")]
+    Open {
+        url: String,
+        /// Wait for a selector to appear after navigating
+        #[arg(long)]
+        wait_for: Option<String>,
+        /// Wait for a load state (load, domcontentloaded, networkidle0, networkidle2) after navigating
+        #[arg(long)]
+        wait_load: Option<String>,
+        /// User agent string or preset (googlebot, iphone, android)
+        #[arg(long)]
+        user_agent: Option<String>,
+        /// Accept-Language to send with the user agent override
+        #[arg(long, requires = "user_agent")]
+        accept_language: Option<String>,
+        /// Open in a new tab instead of replacing the current page
+        #[arg(long)]
+        new_tab: bool,
+        /// Load the page with its Content-Security-Policy disabled
+        #[arg(long)]
+        bypass_csp: bool,
+    },
+    /// Go back in history and wait for the page to load
+    Back {
+        /// Return as soon as the navigation starts
+        #[arg(long)]
+        no_wait: bool,
+    },
+    /// Go forward in history and wait for the page to load
+    Forward {
+        /// Return as soon as the navigation starts
+        #[arg(long)]
+        no_wait: bool,
+    },
+    /// Reload current page and wait for it to load
+    Reload {
+        /// Bypass the cache (hard reload)
+        #[arg(long)]
+        hard: bool,
+        /// Wait for the load event (the default; kept for older scripts)
+        #[arg(long, hide = true, conflicts_with = "no_wait")]
+        wait: bool,
+        /// Return as soon as the reload starts
+        #[arg(long)]
+        no_wait: bool,
+    },
+    /// Show browser and protocol versions
+    Info,
+    /// List session history, or jump to an entry
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryCommand>,
+    },
+    /// Close the current tab (the browser and its other tabs stay open)
     Close,
+    /// Shut down the whole browser, closing every tab
+    #[command(visible_alias = "exit")]
+    Quit,
     /// Click an element
-    Click { selector: String },
+    Click {
+        #[arg(required_unless_present = "ref_id")]
+        selector: Option<String>,
+        /// Element ref from the last `snapshot --interactive` (e.g. e3)
+        #[arg(long = "ref", conflicts_with_all = ["selector", "nth", "all"])]
+        ref_id: Option<String>,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long, conflicts_with = "all")]
+        nth: Option<usize>,
+        /// Click every matching element instead of the first
+        #[arg(long)]
+        all: bool,
+        /// With --all, succeed even when nothing matches
+        #[arg(long, requires = "all")]
+        allow_empty: bool,
+    },
+    /// Tap an element with touch events
+    Tap { selector: String },
+    /// Swipe with touch events in a direction or between two points
+    Swipe {
+        direction: Option<touch::SwipeDirection>,
+        /// Start point as x,y (default: element or viewport center)
+        #[arg(long, value_parser = touch::parse_point)]
+        from: Option<(f64, f64)>,
+        /// End point as x,y (overrides direction)
+        #[arg(long, value_parser = touch::parse_point)]
+        to: Option<(f64, f64)>,
+        /// Start from the center of this element
+        #[arg(long)]
+        selector: Option<String>,
+        /// Swipe distance in pixels when using a direction
+        #[arg(long, default_value_t = 300.0)]
+        distance: f64,
+        /// Number of touchMove steps
+        #[arg(long, default_value_t = 10)]
+        steps: u32,
+    },
     /// Type text into an element
-    Type { selector: String, text: String },
+    Type {
+        /// Element selector (with --ref, the text to type)
+        #[arg(value_name = "SELECTOR")]
+        target: String,
+        text: Option<String>,
+        /// Element ref from the last `snapshot --interactive` (e.g. e3)
+        #[arg(long = "ref", conflicts_with = "nth")]
+        ref_id: Option<String>,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+        /// Empty the field before typing instead of appending
+        #[arg(long)]
+        clear_first: bool,
+    },
     /// Clear and fill an element. For file inputs, attaches the file path.
-    Fill { selector: String, text: String },
+    Fill {
+        /// Element selector (with --ref, the text to fill)
+        #[arg(value_name = "SELECTOR")]
+        target: String,
+        text: Option<String>,
+        /// Element ref from the last `snapshot --interactive` (e.g. e3)
+        #[arg(long = "ref", conflicts_with = "nth")]
+        ref_id: Option<String>,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+    },
     /// Attach one or more files to a file input
     Attach {
         selector: String,
         files: Vec<String>,
     },
+    /// Drop files from disk onto an element (for drag-and-drop upload zones)
+    DragFile {
+        selector: String,
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+    },
+    /// Click an element and wait for the download it triggers
+    Download {
+        selector: String,
+        /// Directory to save the download in
+        #[arg(default_value = "/tmp/claude/downloads")]
+        dir: std::path::PathBuf,
+        /// Milliseconds to wait for the download to finish
+        #[arg(long, default_value_t = 30000)]
+        timeout: u64,
+    },
+    /// Scroll the page, a scroll container, or an element into view
+    Scroll {
+        /// Element to scroll into view, or container to scroll with --by
+        selector: Option<String>,
+        /// Scroll the element into view
+        #[arg(long, requires = "selector", conflicts_with = "by")]
+        into_view: bool,
+        /// Vertical alignment for --into-view
+        #[arg(long, value_enum, default_value = "center")]
+        align: scroll::ScrollAlign,
+        /// Scroll vertically by this many pixels
+        #[arg(long, allow_hyphen_values = true)]
+        by: Option<f64>,
+        /// Milliseconds to wait for scrolling to settle
+        #[arg(long, default_value_t = 5000)]
+        timeout: u64,
+    },
+    /// Dispatch raw mouse events at viewport coordinates
+    Mouse {
+        #[command(subcommand)]
+        action: MouseCommand,
+    },
     /// Press a key
     #[command(visible_alias = "key")]
     Press { key: String },
-    /// Take a screenshot (JPEG format, quality 15)
+    /// Take a screenshot (JPEG quality 15; PNG for a .png path or with --baseline)
     Screenshot {
         /// Output path
         #[arg(default_value = "/tmp/claude/screenshot.jpg")]
@@ -62,14 +295,91 @@ enum Command {
         /// Full page screenshot
         #[arg(short, long)]
         full: bool,
+        /// Device scale factor to render at (1-4)
+        #[arg(long)]
+        dsf: Option<f64>,
+        /// Compare against this PNG baseline; fails if it differs beyond --threshold.
+        /// The capture is saved as PNG (the path's extension becomes .png)
+        #[arg(long)]
+        baseline: Option<std::path::PathBuf>,
+        /// Write an image highlighting changed pixels in red
+        #[arg(long, requires = "baseline")]
+        diff_out: Option<std::path::PathBuf>,
+        /// Largest fraction of changed pixels (0-1) that still passes
+        #[arg(long, default_value_t = 0.0, requires = "baseline")]
+        threshold: f64,
+        /// Capture every match of this selector to numbered files (path-0.jpg, ...)
+        #[arg(long, value_name = "SELECTOR", conflicts_with_all = ["full", "baseline"])]
+        elements: Option<String>,
+    },
+    /// Re-run the commands recorded in a --transcript file, in order
+    Replay {
+        transcript: std::path::PathBuf,
+        /// Continue after a command fails
+        #[arg(long)]
+        keep_going: bool,
+    },
+    /// Save the page with its resources as a single MHTML file
+    Mhtml {
+        #[arg(default_value = "/tmp/claude/page.mhtml")]
+        path: std::path::PathBuf,
+    },
+    /// Print the raw server response for the current page (reloads it)
+    Source,
+    /// Evaluate JavaScript (several scripts run in order and print an array)
+    Eval {
+        #[arg(required = true, value_name = "SCRIPT")]
+        scripts: Vec<String>,
+        /// Print string results without JSON quoting
+        #[arg(long, conflicts_with = "pretty")]
+        raw: bool,
+        /// Pretty-print the result even with --json
+        #[arg(long)]
+        pretty: bool,
+        /// Run in an isolated world: DOM access works, page JS globals are not visible
+        #[arg(long)]
+        isolated: bool,
+        /// Bind name=JSON (repeatable); the script becomes a function body using the names
+        #[arg(long = "arg", value_name = "NAME=VALUE", value_parser = commands::parse_eval_arg)]
+        args: Vec<commands::EvalArg>,
+    },
+    /// Evaluate JavaScript repeatedly, printing each result
+    Poll {
+        script: String,
+        /// Stop once this expression is truthy (`value` holds the latest result)
+        #[arg(long)]
+        until: Option<String>,
+        /// Milliseconds between evaluations
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+        /// Stop after this many evaluations (fails if --until was not met)
+        #[arg(long)]
+        max: Option<u64>,
+    },
+    /// Record a screencast as a directory of JPEG frames
+    Record {
+        /// Directory to write frames to
+        #[arg(default_value = "/tmp/claude/recording")]
+        path: std::path::PathBuf,
+        /// Recording length in milliseconds
+        #[arg(long, default_value_t = 5000)]
+        duration: u64,
+        /// Maximum frames per second to keep
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..=60))]
+        fps: u32,
     },
-    /// Evaluate JavaScript
-    Eval { script: String },
     /// Get page information
     Get {
         #[command(subcommand)]
         what: GetCommand,
     },
+    /// Print whether any element matches the selector (true/false)
+    Exists {
+        selector: String,
+        /// Re-read every N ms until two readings agree (for pages still rendering)
+        #[arg(long, value_name = "MS")]
+        settle: Option<u64>,
+    },
     /// Manage tabs
     Tabs {
         #[command(subcommand)]
@@ -82,9 +392,33 @@ enum Command {
         /// Wait for URL pattern
         #[arg(short, long)]
         url: Option<String>,
-        /// Wait for load state
+        /// Wait for load state (load, domcontentloaded, networkidle0, networkidle2)
         #[arg(short, long)]
         load: Option<String>,
+        /// Wait until a JavaScript expression is truthy
+        #[arg(long)]
+        function: Option<String>,
+        /// Wait until the DOM and resource loads are quiet for N ms (default 500)
+        #[arg(long, num_args = 0..=1, default_missing_value = "500", value_name = "QUIET_MS")]
+        stable: Option<u64>,
+        /// Element to wait for (repeatable); resolves on the first selector or text that matches
+        #[arg(
+            long = "selector",
+            visible_alias = "any",
+            value_name = "SELECTOR",
+            conflicts_with_all = ["target", "url", "load", "function", "stable"]
+        )]
+        selectors: Vec<String>,
+        /// Page text to wait for (repeatable); resolves on the first selector or text that matches
+        #[arg(
+            long = "text",
+            value_name = "TEXT",
+            conflicts_with_all = ["target", "url", "load", "function", "stable"]
+        )]
+        texts: Vec<String>,
+        /// Milliseconds to wait for a --selector/--text condition
+        #[arg(long, default_value_t = 30000)]
+        timeout: u64,
     },
     /// Get page accessibility/React tree snapshot
     Snapshot {
@@ -109,15 +443,190 @@ enum Command {
         /// Minimized DOM tree (collapses wrapper chains)
         #[arg(long)]
         mini: bool,
+        /// Output format for --full/--mini
+        #[arg(long, value_enum, default_value_t)]
+        format: snapshot::SnapshotFormat,
+        /// Truncate output to at most N characters
+        #[arg(long)]
+        max_chars: Option<usize>,
+        /// Report missing accessible names, alt text and labels (ARIA tree), with a --ref for each
+        #[arg(long)]
+        audit: bool,
+        /// Stop after N lines, summarizing how many nodes were omitted
+        #[arg(long)]
+        max_lines: Option<usize>,
+        /// Snapshot only the subtree of the first element matching this selector
+        #[arg(long)]
+        root: Option<String>,
+        /// Order of --filter matches (React snapshots)
+        #[arg(long, value_enum, default_value_t, requires_all = ["filter", "react"])]
+        order: snapshot::SnapshotOrder,
+        /// Drop --filter matches that render the same as an earlier one (React snapshots)
+        #[arg(long, requires_all = ["filter", "react"])]
+        dedup: bool,
     },
     /// Inspect Runtime console and exception events
     Runtime {
         #[command(subcommand)]
         action: RuntimeCommand,
     },
+    /// Manage isolated (incognito-like) browser contexts
+    Context {
+        #[command(subcommand)]
+        action: ContextCommand,
+    },
+    /// Emulate device conditions (session-scoped)
+    Emulate {
+        #[command(subcommand)]
+        action: EmulateCommand,
+    },
+    /// Zoom the page like Ctrl+Plus, shrinking the CSS viewport (session-scoped)
+    Zoom {
+        /// Zoom factor, clamped to 0.25-5 (1 = 100%)
+        #[arg(required_unless_present = "reset")]
+        factor: Option<f64>,
+        /// Restore 100% zoom
+        #[arg(long, conflicts_with = "factor")]
+        reset: bool,
+        /// Keep the session (and zoom) alive for this many milliseconds
+        #[arg(long)]
+        hold: Option<u64>,
+    },
+    /// Grant or reset browser permissions
+    Permissions {
+        #[command(subcommand)]
+        action: PermissionsCommand,
+    },
+    /// Pause page JavaScript so the DOM holds still (until `resume`, Ctrl-C or --duration)
+    Freeze {
+        /// Resume automatically after this many milliseconds
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+    /// Resume JavaScript paused by `freeze`
+    Resume,
+    /// Answer requests matching a URL pattern with a canned response (runs until Ctrl-C)
+    Mock {
+        /// URL pattern with `*` wildcards, e.g. "*/api/users*"
+        url_pattern: String,
+        /// HTTP status code
+        #[arg(long, default_value_t = 200)]
+        status: u16,
+        /// Response body
+        #[arg(long, default_value = "")]
+        body: String,
+        /// Content-Type header
+        #[arg(long, default_value = "application/json")]
+        content_type: String,
+        /// Stop after this many milliseconds
+        #[arg(long)]
+        duration: Option<u64>,
+        /// Reload the page once interception is active
+        #[arg(long)]
+        reload: bool,
+    },
+    /// Wait for network activity
+    Net {
+        #[command(subcommand)]
+        action: NetCommand,
+    },
+    /// Export or import cookies as JSON
+    Cookies {
+        /// Use the cookie jar of this browser context (from `context new`/`context list`)
+        #[arg(long, value_name = "ID")]
+        context: Option<String>,
+        #[command(subcommand)]
+        action: CookiesCommand,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum HistoryCommand {
+    /// List history entries (default)
+    List,
+    /// Navigate to a history entry by index
+    Go { index: usize },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum MouseCommand {
+    /// Move the pointer
+    Move(input::MouseArgs),
+    /// Press and release a button
+    Click(input::MouseArgs),
+    /// Press a button
+    Down(input::MouseArgs),
+    /// Release a button
+    Up(input::MouseArgs),
+}
+
+#[derive(Subcommand, Clone)]
+pub enum EmulateCommand {
+    /// Throttle the CPU (1 = no throttle, 4 = 4x slowdown)
+    Cpu {
+        /// Slowdown factor
+        #[arg(long, required_unless_present = "reset")]
+        rate: Option<f64>,
+        /// Clear throttling (same as --rate 1)
+        #[arg(long, conflicts_with = "rate")]
+        reset: bool,
+        /// Keep the session (and throttling) alive for this many milliseconds
+        #[arg(long)]
+        hold: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum PermissionsCommand {
+    /// Grant permissions (e.g. notifications, geolocation, clipboard-read)
+    Grant {
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// Origin to grant for (default: all origins)
+        #[arg(long)]
+        origin: Option<String>,
+    },
+    /// Reset all permission overrides
+    Reset,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum NetCommand {
+    /// Block until a response whose URL matches arrives
+    WaitResponse {
+        /// URL substring, or a pattern with `*` wildcards
+        url_pattern: String,
+        /// Only match this request method (GET, POST, ...)
+        #[arg(long)]
+        method: Option<String>,
+        /// Only match this HTTP status
+        #[arg(long)]
+        status: Option<u16>,
+        /// Milliseconds to wait before failing
+        #[arg(long, default_value_t = 30000)]
+        timeout: u64,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum CookiesCommand {
+    /// Write all browser cookies to a JSON file
+    Export { file: std::path::PathBuf },
+    /// Set cookies from a JSON file written by export
+    Import { file: std::path::PathBuf },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ContextCommand {
+    /// Create an isolated context and open a tab in it
+    New { url: Option<String> },
+    /// List contexts created by browser-cli
+    List,
+    /// Dispose a context (default: all created by browser-cli)
+    Dispose { id: Option<String> },
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 pub enum RuntimeCommand {
     /// Capture console API calls
     Console {
@@ -127,6 +636,18 @@ pub enum RuntimeCommand {
         /// Milliseconds to collect events
         #[arg(long, default_value_t = 1500)]
         wait_ms: u64,
+        /// Only include these levels (comma-separated, e.g. error,warn)
+        #[arg(long, value_delimiter = ',')]
+        level: Vec<String>,
+        /// Only include messages containing this text
+        #[arg(long)]
+        grep: Option<String>,
+        /// Only include events from the last N milliseconds before capture started
+        #[arg(long)]
+        since: Option<u64>,
+        /// Write events as JSON lines to this file instead of stdout
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
     },
     /// Capture runtime exceptions
     Exceptions {
@@ -139,58 +660,492 @@ pub enum RuntimeCommand {
     },
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 pub enum GetCommand {
     /// Get page title
     Title,
     /// Get current URL
     Url,
     /// Get element text
-    Text { selector: Option<String> },
+    Text {
+        selector: Option<String>,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long, requires = "selector", conflicts_with = "all")]
+        nth: Option<usize>,
+        /// Get the text of every match (a JSON array with --json)
+        #[arg(long, requires = "selector", conflicts_with = "markdown")]
+        all: bool,
+        /// Separator between matches with --all (\t and \n escapes allowed)
+        #[arg(long, requires = "all", default_value = "\\n")]
+        join: String,
+        /// Truncate output to at most N characters
+        #[arg(long)]
+        max_chars: Option<usize>,
+        /// Collapse runs of whitespace, trim lines and drop repeated blank lines
+        #[arg(long, conflicts_with = "markdown")]
+        trim: bool,
+        /// Convert the subtree to Markdown (headings, links, lists, emphasis)
+        #[arg(long)]
+        markdown: bool,
+        /// Only text from rendered elements (skips zero-size, hidden and aria-hidden)
+        #[arg(long, conflicts_with = "markdown")]
+        visible_only: bool,
+    },
     /// Get element HTML
-    Html { selector: String },
-    /// Get input value
-    Value { selector: String },
+    Html {
+        selector: String,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long, conflicts_with = "all")]
+        nth: Option<usize>,
+        /// Include the element's own tag (outerHTML)
+        #[arg(long)]
+        outer: bool,
+        /// Return a JSON array with the markup of every match
+        #[arg(long)]
+        all: bool,
+        /// Truncate each result to at most N characters
+        #[arg(long)]
+        max_chars: Option<usize>,
+    },
+    /// Get element HTML including its own tag and attributes
+    OuterHtml {
+        selector: String,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+        /// Truncate output to at most N characters
+        #[arg(long)]
+        max_chars: Option<usize>,
+    },
+    /// Get input value (checked state for checkboxes/radios, selected values for multi-selects)
+    Value {
+        selector: String,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+    },
     /// Get element attribute
-    Attr { selector: String, name: String },
+    Attr {
+        selector: String,
+        name: String,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+    },
+    /// Get the computed accessible role, name and states of an element
+    Aria {
+        selector: String,
+        /// Use the nth (0-based) match instead of the first
+        #[arg(long)]
+        nth: Option<usize>,
+    },
     /// Count matching elements
-    Count { selector: String },
+    Count {
+        selector: String,
+        /// Re-read every N ms until two readings agree (for pages still rendering)
+        #[arg(long, value_name = "MS")]
+        settle: Option<u64>,
+    },
+    /// Generate a CSS selector for a snapshot ref
+    Selector {
+        /// Ref from a snapshot (e.g. e1)
+        #[arg(long = "ref")]
+        ref_id: String,
+    },
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 pub enum TabsCommand {
     /// List open tabs
-    List,
+    List {
+        /// Include non-page targets (workers, background pages, ...)
+        #[arg(long)]
+        all: bool,
+    },
     /// Open new tab
-    New { url: Option<String> },
-    /// Close tab
-    Close { index: Option<usize> },
+    New {
+        url: Option<String>,
+        /// Open without activating the new tab
+        #[arg(long)]
+        background: bool,
+    },
+    /// Close tab by index, or several with --all / --match
+    Close {
+        #[arg(conflicts_with_all = ["all", "pattern"])]
+        index: Option<usize>,
+        /// Close every page tab
+        #[arg(long)]
+        all: bool,
+        /// Close tabs whose URL contains this text
+        #[arg(long = "match", value_name = "TEXT")]
+        pattern: Option<String>,
+        /// Also close about: and chrome:// tabs
+        #[arg(long)]
+        force: bool,
+    },
     /// Switch to tab by index
     Switch { index: usize },
+    /// Move a tab into a new window (the page is reopened there)
+    Move {
+        index: usize,
+        /// Detach the tab into its own window
+        #[arg(long)]
+        to_new_window: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let port = cli.port;
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    cli.json |= cli.format != output::OutputFormat::Text;
     let json = cli.json;
+    let transcript = cli.transcript.clone();
+    let started_ms = output::now_millis();
+    let result = run(cli).await;
+    let failure = result
+        .as_ref()
+        .err()
+        .map(|err| (errors::classify(err), err));
+    if let Some(path) = transcript {
+        let entry = transcript::entry(
+            matches.subcommand_name().unwrap_or_default(),
+            &transcript::recorded_args(std::env::args().skip(1)),
+            started_ms,
+            output::now_millis() - started_ms,
+            failure.map(|(kind, err)| (kind, format!("{:#}", err))),
+        );
+        if let Err(err) = transcript::append(&path, &entry) {
+            eprintln!("warning: {:#}", err);
+        }
+    }
+    if let Some((kind, err)) = failure {
+        if json {
+            output::print_error_json(err, kind);
+        } else {
+            eprintln!("Error: {:?}", err);
+        }
+        std::process::exit(kind.exit_code());
+    }
+    Ok(())
+}
 
-    match cli.command {
-        Command::Open { url } => commands::cmd_open(port, url, json).await,
-        Command::Back => commands::cmd_simple_page(port, "Page.goBack", "Back").await,
-        Command::Forward => commands::cmd_simple_page(port, "Page.goForward", "Forward").await,
-        Command::Reload => commands::cmd_simple_page(port, "Page.reload", "Reloaded").await,
-        Command::Close => commands::cmd_simple_page(port, "Page.close", "Closed").await,
-        Command::Click { selector } => commands::cmd_click(port, &selector).await,
-        Command::Type { selector, text } => commands::cmd_type(port, &selector, &text).await,
-        Command::Fill { selector, text } => commands::cmd_fill(port, &selector, &text).await,
-        Command::Attach { selector, files } => commands::cmd_attach(port, &selector, &files).await,
-        Command::Press { key } => commands::cmd_press(port, &key).await,
-        Command::Screenshot { path, full } => commands::cmd_screenshot(port, &path, full).await,
-        Command::Eval { script } => commands::cmd_eval(port, &script, json).await,
+async fn run(cli: Cli) -> Result<()> {
+    cdp::set_launch_options(cdp::LaunchOptions {
+        timeout_ms: cli.launch_timeout,
+        no_sandbox: cli.no_sandbox,
+        connect_retries: cli.connect_retry,
+        profile: cli.profile.clone(),
+        ignore_https_errors: cli.ignore_https_errors,
+    });
+    if cli.ignore_https_errors {
+        eprintln!(
+            "warning: --ignore-https-errors disables certificate checks; traffic can be intercepted"
+        );
+    }
+    selector::set_engine(cli.selector_engine);
+    selector::set_select_timeout(cli.select_timeout);
+    output::set_format(cli.format);
+    cdp::set_bypass_csp(cli.bypass_csp);
+    cdp::set_target_id(cli.target_id.clone());
+    cdp::set_dump_protocol(cli.dump_protocol);
+    let viewport = match cli.viewport {
+        Some(viewport) => Some(emulate::hold_viewport(cli.port, viewport).await?),
+        None => None,
+    };
+    let result = run_with_retries(&cli).await;
+    if let Some(session) = viewport {
+        // The override also ends when the session detaches, and the command may
+        // have closed the tab, so a failed reset is not worth reporting.
+        let _ = emulate::release_viewport(session).await;
+    }
+    result
+}
+
+fn parse_viewport_arg(value: &str) -> Result<emulate::Viewport, String> {
+    emulate::parse_viewport(value).map_err(|err| err.to_string())
+}
+
+async fn run_with_retries(cli: &Cli) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match dispatch(cli.command.clone(), cli.port, cli.json).await {
+            Err(err) if attempt < cli.retries => {
+                attempt += 1;
+                eprintln!(
+                    "Attempt {} of {} failed: {:#}; retrying in {}ms",
+                    attempt,
+                    cli.retries + 1,
+                    err,
+                    cli.retry_delay
+                );
+                tokio::time::sleep(tokio::time::Duration::from_millis(cli.retry_delay)).await;
+            }
+            Ok(()) if cli.wait_after > 0 && cli.command.is_input() => {
+                tokio::time::sleep(tokio::time::Duration::from_millis(cli.wait_after)).await;
+                return Ok(());
+            }
+            result => return result,
+        }
+    }
+}
+
+impl GetCommand {
+    /// The element this read resolves, which `--select-timeout` waits for.
+    /// Counts are answered immediately.
+    pub(crate) fn element_target(&self) -> Option<(&str, Option<usize>)> {
+        match self {
+            GetCommand::Text {
+                selector: Some(selector),
+                nth,
+                ..
+            }
+            | GetCommand::Html { selector, nth, .. }
+            | GetCommand::OuterHtml { selector, nth, .. }
+            | GetCommand::Value { selector, nth, .. }
+            | GetCommand::Attr { selector, nth, .. }
+            | GetCommand::Aria { selector, nth } => Some((selector, *nth)),
+            _ => None,
+        }
+    }
+}
+
+impl Command {
+    /// Commands that drive the page with user input, which `--wait-after` follows.
+    fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Command::Click { .. }
+                | Command::Tap { .. }
+                | Command::Swipe { .. }
+                | Command::Type { .. }
+                | Command::Fill { .. }
+                | Command::Attach { .. }
+                | Command::DragFile { .. }
+                | Command::Scroll { .. }
+                | Command::Mouse { .. }
+                | Command::Press { .. }
+        )
+    }
+}
+
+/// `type`/`fill` take `<SELECTOR> <TEXT>`, or `--ref <REF> <TEXT>` with the text
+/// as the only positional argument.
+async fn selector_and_text(
+    port: u16,
+    target: String,
+    text: Option<String>,
+    ref_id: Option<String>,
+) -> Result<(String, String)> {
+    match (ref_id, text) {
+        (Some(ref_id), None) => Ok((refs::resolve_ref(port, &ref_id).await?, target)),
+        (None, Some(text)) => Ok((target, text)),
+        (Some(_), Some(_)) => anyhow::bail!("With --ref, pass only the text"),
+        (None, None) => anyhow::bail!("Missing text: expected <SELECTOR> <TEXT>"),
+    }
+}
+
+async fn dispatch(command: Command, port: u16, json: bool) -> Result<()> {
+    match command {
+        Command::Open {
+            url,
+            wait_for,
+            wait_load,
+            user_agent,
+            accept_language,
+            new_tab,
+            bypass_csp,
+        } => {
+            let opts = commands::OpenOptions {
+                wait_for,
+                wait_load,
+                user_agent,
+                accept_language,
+                new_tab,
+                bypass_csp,
+            };
+            commands::cmd_open(port, url, &opts, json).await
+        }
+        Command::Back { no_wait } => {
+            navigation::cmd_history_step(port, navigation::HistoryStep::Back, !no_wait, json).await
+        }
+        Command::Forward { no_wait } => {
+            navigation::cmd_history_step(port, navigation::HistoryStep::Forward, !no_wait, json)
+                .await
+        }
+        Command::Reload { hard, no_wait, .. } => {
+            navigation::cmd_reload(port, hard, !no_wait, json).await
+        }
+        Command::Info => commands::cmd_info(port, json).await,
+        Command::History { action } => commands::cmd_history(port, action.as_ref(), json).await,
+        Command::Close => commands::cmd_simple_page(port, "Page.close", "Closed", json).await,
+        Command::Quit => commands::cmd_quit(port, json).await,
+        Command::Click {
+            selector,
+            ref_id,
+            nth,
+            all,
+            allow_empty,
+        } => {
+            let selector = match ref_id {
+                Some(ref_id) => refs::resolve_ref(port, &ref_id).await?,
+                None => selector.unwrap_or_default(),
+            };
+            commands::cmd_click(port, &selector, nth, all, allow_empty, json).await
+        }
+        Command::Tap { selector } => touch::cmd_tap(port, &selector, json).await,
+        Command::Swipe {
+            direction,
+            from,
+            to,
+            selector,
+            distance,
+            steps,
+        } => {
+            let opts = touch::SwipeOptions {
+                direction,
+                from,
+                to,
+                selector,
+                distance,
+                steps,
+            };
+            touch::cmd_swipe(port, &opts, json).await
+        }
+        Command::Type {
+            target,
+            text,
+            ref_id,
+            nth,
+            clear_first,
+        } => {
+            let (selector, text) = selector_and_text(port, target, text, ref_id).await?;
+            commands::cmd_type(port, &selector, nth, &text, clear_first, json).await
+        }
+        Command::Fill {
+            target,
+            text,
+            ref_id,
+            nth,
+        } => {
+            let (selector, text) = selector_and_text(port, target, text, ref_id).await?;
+            commands::cmd_fill(port, &selector, nth, &text, json).await
+        }
+        Command::Attach { selector, files } => {
+            commands::cmd_attach(port, &selector, &files, json).await
+        }
+        Command::DragFile {
+            selector,
+            files,
+            nth,
+        } => file_drop::cmd_drag_file(port, &selector, nth, &files, json).await,
+        Command::Download {
+            selector,
+            dir,
+            timeout,
+        } => download::cmd_download(port, &selector, &dir, timeout, json).await,
+        Command::Scroll {
+            selector,
+            into_view,
+            align,
+            by,
+            timeout,
+        } => {
+            let opts = scroll::ScrollOptions {
+                selector,
+                into_view,
+                align,
+                by,
+                timeout_ms: timeout,
+            };
+            scroll::cmd_scroll(port, &opts, json).await
+        }
+        Command::Mouse { action } => input::cmd_mouse(port, &action, json).await,
+        Command::Press { key } => commands::cmd_press(port, &key, json).await,
+        Command::Screenshot {
+            path,
+            full,
+            dsf,
+            baseline,
+            diff_out,
+            threshold,
+            elements,
+        } => {
+            let path = output::prepare_output_path(&path)?;
+            if let Some(selector) = elements {
+                return commands::cmd_screenshot_elements(port, &path, &selector, dsf, json).await;
+            }
+            let diff_out = diff_out.map(output::prepare_output_path).transpose()?;
+            let diff = baseline.map(|baseline| screenshot_diff::DiffOptions {
+                baseline,
+                diff_out,
+                threshold,
+            });
+            commands::cmd_screenshot(port, &path, full, dsf, diff.as_ref(), json).await
+        }
+        Command::Replay {
+            transcript,
+            keep_going,
+        } => transcript::cmd_replay(&transcript, keep_going, json).await,
+        Command::Mhtml { path } => {
+            let path = output::prepare_output_path(&path)?;
+            commands::cmd_mhtml(port, &path, json).await
+        }
+        Command::Source => source::cmd_source(port).await,
+        Command::Eval {
+            scripts,
+            raw,
+            pretty,
+            isolated,
+            args,
+        } => commands::cmd_eval(port, &scripts, json && !pretty, raw, isolated, &args).await,
+        Command::Poll {
+            script,
+            until,
+            interval,
+            max,
+        } => {
+            let opts = commands::PollOptions {
+                until,
+                interval_ms: interval,
+                max,
+            };
+            commands::cmd_poll(port, &script, &opts, json).await
+        }
+        Command::Record {
+            path,
+            duration,
+            fps,
+        } => {
+            let path = output::expand_timestamp(&path, output::now_millis());
+            record::cmd_record(port, &path, duration, fps, json).await
+        }
         Command::Get { what } => commands::cmd_get(port, &what, json).await,
+        Command::Exists { selector, settle } => {
+            commands::cmd_exists(port, &selector, settle, json).await
+        }
         Command::Tabs { action } => commands::cmd_tabs(port, &action, json).await,
-        Command::Wait { target, url, load } => commands::cmd_wait(port, target, url, load).await,
+        Command::Wait {
+            target,
+            url,
+            load,
+            function,
+            stable,
+            selectors,
+            texts,
+            timeout,
+        } => {
+            let conditions: Vec<commands::WaitCondition> = selectors
+                .into_iter()
+                .map(commands::WaitCondition::Selector)
+                .chain(texts.into_iter().map(commands::WaitCondition::Text))
+                .collect();
+            if conditions.is_empty() {
+                commands::cmd_wait(port, target, url, load, function, stable, json).await
+            } else {
+                commands::cmd_wait_any(port, &conditions, timeout, json).await
+            }
+        }
         Command::Snapshot {
             interactive,
             compact,
@@ -199,10 +1154,66 @@ async fn main() -> Result<()> {
             filter,
             full,
             mini,
+            format,
+            max_chars,
+            audit,
+            max_lines,
+            root,
+            order,
+            dedup,
         } => {
-            commands::cmd_snapshot(port, interactive, compact, react, depth, filter, full, mini)
-                .await
+            let opts = snapshot::SnapshotOptions {
+                interactive,
+                compact,
+                react,
+                max_depth: depth,
+                filter,
+                full,
+                mini,
+                format,
+                audit,
+                max_lines,
+                root,
+                order,
+                dedup,
+            };
+            commands::cmd_snapshot(port, &opts, max_chars).await
         }
         Command::Runtime { action } => runtime::cmd_runtime(port, &action, json).await,
+        Command::Context { action } => context::cmd_context(port, &action, json).await,
+        Command::Emulate { action } => emulate::cmd_emulate(port, &action, json).await,
+        Command::Zoom {
+            factor,
+            reset,
+            hold,
+        } => {
+            let factor = if reset { None } else { factor };
+            emulate::cmd_zoom(port, factor, hold, json).await
+        }
+        Command::Permissions { action } => permissions::cmd_permissions(port, &action, json).await,
+        Command::Mock {
+            url_pattern,
+            status,
+            body,
+            content_type,
+            duration,
+            reload,
+        } => {
+            let opts = network::MockOptions {
+                pattern: url_pattern,
+                status,
+                body,
+                content_type,
+                duration_ms: duration,
+                reload,
+            };
+            network::cmd_mock(port, &opts, json).await
+        }
+        Command::Freeze { duration } => debugger::cmd_freeze(port, duration, json).await,
+        Command::Resume => debugger::cmd_resume(port, json).await,
+        Command::Net { action } => network::cmd_net(port, &action, json).await,
+        Command::Cookies { context, action } => {
+            cookies::cmd_cookies(port, context.as_deref(), &action, json).await
+        }
     }
 }
@@ -0,0 +1,79 @@
+((root) => {
+    if (!root) return '';
+    const SKIP = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'SVG', 'IFRAME']);
+    const BLOCK = new Set(['P', 'DIV', 'SECTION', 'ARTICLE', 'MAIN', 'HEADER', 'FOOTER', 'ASIDE', 'NAV', 'FIGURE', 'TABLE', 'TR', 'FORM', 'BLOCKQUOTE']);
+
+    const inline = (text) => text.replace(/\s+/g, ' ');
+
+    function visible(el) {
+      const style = getComputedStyle(el);
+      return style.display !== 'none' && style.visibility !== 'hidden';
+    }
+
+    function children(el, ctx) {
+      let out = '';
+      for (const child of el.childNodes) out += convert(child, ctx);
+      return out;
+    }
+
+    function convert(node, ctx) {
+      if (node.nodeType === Node.TEXT_NODE) return ctx.pre ? node.textContent : inline(node.textContent);
+      if (node.nodeType !== Node.ELEMENT_NODE) return '';
+      const el = node;
+      const tag = el.tagName;
+      if (SKIP.has(tag) || !visible(el)) return '';
+
+      const heading = /^H([1-6])$/.exec(tag);
+      if (heading) return '\n\n' + '#'.repeat(Number(heading[1])) + ' ' + children(el, ctx).trim() + '\n\n';
+
+      switch (tag) {
+        case 'BR': return '\n';
+        case 'HR': return '\n\n---\n\n';
+        case 'A': {
+          const text = children(el, ctx).trim();
+          const href = el.getAttribute('href');
+          return href && text ? `[${text}](${el.href})` : text;
+        }
+        case 'IMG': {
+          const alt = el.getAttribute('alt') || '';
+          return alt ? `![${alt}](${el.src})` : '';
+        }
+        case 'STRONG': case 'B': {
+          const text = children(el, ctx).trim();
+          return text ? `**${text}**` : '';
+        }
+        case 'EM': case 'I': {
+          const text = children(el, ctx).trim();
+          return text ? `_${text}_` : '';
+        }
+        case 'CODE':
+          return ctx.pre ? el.textContent : '`' + el.textContent + '`';
+        case 'PRE':
+          return '\n\n```\n' + children(el, { ...ctx, pre: true }).replace(/\n+$/, '') + '\n```\n\n';
+        case 'UL': case 'OL': {
+          const indent = '  '.repeat(ctx.depth);
+          let index = 0;
+          let out = '\n';
+          for (const li of el.children) {
+            if (li.tagName !== 'LI' || !visible(li)) continue;
+            index++;
+            const marker = tag === 'OL' ? `${index}.` : '-';
+            const body = children(li, { ...ctx, depth: ctx.depth + 1 }).trim();
+            out += `${indent}${marker} ${body}\n`;
+          }
+          return out + '\n';
+        }
+        case 'TD': case 'TH':
+          return children(el, ctx).trim() + ' | ';
+      }
+      const body = children(el, ctx);
+      return BLOCK.has(tag) ? '\n\n' + body.trim() + '\n\n' : body;
+    }
+
+    return convert(root, { depth: 0, pre: false })
+      .split('\n')
+      .map((line) => line.replace(/[ \t]+$/, ''))
+      .join('\n')
+      .replace(/\n{3,}/g, '\n\n')
+      .trim();
+})(__ROOT__)
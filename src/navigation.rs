@@ -0,0 +1,293 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio::time::{Duration, Instant};
+
+use crate::cdp::{self, CdpConnection};
+use crate::errors::ErrorKind;
+use crate::output::print_action;
+
+const NAVIGATION_TIMEOUT_MS: u64 = 30000;
+
+/// How a reload or history navigation finished
+#[derive(Debug, PartialEq)]
+pub(crate) enum NavigationEvent {
+    /// The new document fired `load` (or came back from the back/forward cache)
+    Loaded,
+    /// Only the fragment or history state changed, so no load event follows
+    SameDocument,
+    /// The main document failed to load, e.g. `net::ERR_CONNECTION_REFUSED`
+    Failed(String),
+}
+
+/// Picks the events that end a main-frame navigation out of the Page and
+/// Network event stream.
+pub(crate) struct NavigationTracker {
+    main_frame: String,
+    /// Request ids of main-frame document loads, to match `loadingFailed`
+    documents: HashSet<String>,
+}
+
+impl NavigationTracker {
+    pub(crate) fn new(main_frame: &str) -> Self {
+        Self {
+            main_frame: main_frame.to_string(),
+            documents: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn observe(&mut self, message: &Value) -> Option<NavigationEvent> {
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let str_param = |pointer: &str| params.pointer(pointer).and_then(Value::as_str);
+        let in_main_frame = |pointer: &str| str_param(pointer) == Some(self.main_frame.as_str());
+        match message.get("method").and_then(Value::as_str)? {
+            "Page.loadEventFired" => Some(NavigationEvent::Loaded),
+            "Page.navigatedWithinDocument" if in_main_frame("/frameId") => {
+                Some(NavigationEvent::SameDocument)
+            }
+            "Page.frameNavigated" if in_main_frame("/frame/id") => {
+                if let Some(url) = str_param("/frame/unreachableUrl") {
+                    Some(NavigationEvent::Failed(format!("could not load {}", url)))
+                } else if str_param("/type") == Some("BackForwardCacheRestore") {
+                    Some(NavigationEvent::Loaded)
+                } else {
+                    None
+                }
+            }
+            "Network.requestWillBeSent"
+                if str_param("/type") == Some("Document") && in_main_frame("/frameId") =>
+            {
+                self.documents.insert(str_param("/requestId")?.to_string());
+                None
+            }
+            "Network.loadingFailed" if self.documents.contains(str_param("/requestId")?) => {
+                let error = str_param("/errorText").unwrap_or("unknown error");
+                Some(NavigationEvent::Failed(error.to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A page that could not be loaded is the site's problem, not the browser's, even
+/// when the error reads `net::ERR_CONNECTION_REFUSED`.
+fn navigation_failed(error: &str) -> anyhow::Error {
+    ErrorKind::Other
+        .error(format!("Navigation failed: {}", error))
+        .into()
+}
+
+/// Listens on its own session so no event is lost while the navigating
+/// connection waits for its command's response.
+pub(crate) struct NavigationWatcher {
+    cdp: CdpConnection,
+    tracker: NavigationTracker,
+}
+
+impl NavigationWatcher {
+    pub(crate) async fn start(port: u16) -> Result<Self> {
+        let mut cdp = cdp::connect_active(port).await?;
+        let tree = cdp.send("Page.getFrameTree", serde_json::json!({})).await?;
+        let main_frame = tree
+            .pointer("/frameTree/frame/id")
+            .and_then(Value::as_str)
+            .context("Page.getFrameTree did not return the main frame")?
+            .to_string();
+        cdp.send("Page.enable", serde_json::json!({})).await?;
+        cdp.send("Network.enable", serde_json::json!({})).await?;
+        Ok(Self {
+            cdp,
+            tracker: NavigationTracker::new(&main_frame),
+        })
+    }
+
+    pub(crate) async fn wait(&mut self) -> Result<NavigationEvent> {
+        let deadline = Instant::now() + Duration::from_millis(NAVIGATION_TIMEOUT_MS);
+        loop {
+            let message = tokio::select! {
+                message = self.cdp.recv() => message?.context(ErrorKind::Connection.error("Connection closed while waiting for navigation"))?,
+                _ = tokio::time::sleep_until(deadline) => {
+                    return Err(ErrorKind::Timeout
+                        .error(format!(
+                            "Timeout: page did not finish loading within {}ms",
+                            NAVIGATION_TIMEOUT_MS
+                        ))
+                        .into());
+                }
+            };
+            match self.tracker.observe(&message) {
+                Some(NavigationEvent::Failed(error)) => return Err(navigation_failed(&error)),
+                Some(event) => return Ok(event),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Which way `back`/`forward` move through the history
+#[derive(Clone, Copy)]
+pub enum HistoryStep {
+    Back,
+    Forward,
+}
+
+/// Reload the page, waiting for it to load unless `wait` is false.
+pub async fn cmd_reload(port: u16, hard: bool, wait: bool, json: bool) -> Result<()> {
+    let mut watcher = if wait {
+        Some(NavigationWatcher::start(port).await?)
+    } else {
+        None
+    };
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send("Page.reload", serde_json::json!({ "ignoreCache": hard }))
+        .await?;
+    if let Some(watcher) = watcher.as_mut() {
+        watcher.wait().await?;
+    }
+    let label = if hard { "Hard reloaded" } else { "Reloaded" };
+    print_action(
+        json,
+        "reload",
+        serde_json::json!({ "hard": hard, "waited": wait }),
+        label,
+    );
+    Ok(())
+}
+
+/// Step one entry through the tab's history, waiting for the page unless
+/// `wait` is false.
+pub async fn cmd_history_step(port: u16, step: HistoryStep, wait: bool, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let history = cdp
+        .send("Page.getNavigationHistory", serde_json::json!({}))
+        .await?;
+    let current = history
+        .get("currentIndex")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize;
+    let entries = history
+        .get("entries")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let (target, action, label) = match step {
+        HistoryStep::Back => (current.checked_sub(1), "goBack", "Back"),
+        HistoryStep::Forward => (Some(current + 1), "goForward", "Forward"),
+    };
+    let entry = target
+        .and_then(|index| entries.get(index))
+        .with_context(|| format!("No history entry to go {}", label.to_lowercase()))?;
+    let entry_id = entry
+        .get("id")
+        .cloned()
+        .context("History entry has no id")?;
+
+    let mut watcher = if wait {
+        Some(NavigationWatcher::start(port).await?)
+    } else {
+        None
+    };
+    cdp.send(
+        "Page.navigateToHistoryEntry",
+        serde_json::json!({ "entryId": entry_id }),
+    )
+    .await?;
+    if let Some(watcher) = watcher.as_mut() {
+        watcher.wait().await?;
+    }
+    let url = entry.get("url").and_then(Value::as_str).unwrap_or_default();
+    print_action(
+        json,
+        action,
+        serde_json::json!({ "url": url, "waited": wait }),
+        label,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NavigationEvent, NavigationTracker, navigation_failed};
+    use crate::errors::{ErrorKind, classify};
+    use serde_json::json;
+
+    #[test]
+    fn tracker_reports_main_document_failures_only() {
+        let mut tracker = NavigationTracker::new("main");
+        let sent = |id: &str, frame: &str| {
+            json!({ "method": "Network.requestWillBeSent", "params": {
+                "requestId": id, "frameId": frame, "type": "Document"
+            }})
+        };
+        let failed = |id: &str| {
+            json!({ "method": "Network.loadingFailed", "params": {
+                "requestId": id, "errorText": "net::ERR_CONNECTION_REFUSED"
+            }})
+        };
+        assert_eq!(tracker.observe(&sent("1", "child")), None);
+        assert_eq!(tracker.observe(&sent("2", "main")), None);
+
+        assert_eq!(tracker.observe(&failed("1")), None);
+        assert_eq!(
+            tracker.observe(&failed("2")),
+            Some(NavigationEvent::Failed(
+                "net::ERR_CONNECTION_REFUSED".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn refused_page_load_is_not_a_browser_connection_error() {
+        let mut tracker = NavigationTracker::new("main");
+        tracker.observe(&json!({ "method": "Network.requestWillBeSent", "params": {
+            "requestId": "7", "frameId": "main", "type": "Document"
+        }}));
+        let Some(NavigationEvent::Failed(error)) = tracker.observe(&json!({
+            "method": "Network.loadingFailed",
+            "params": { "requestId": "7", "errorText": "net::ERR_CONNECTION_REFUSED" }
+        })) else {
+            panic!("loadingFailed was not reported");
+        };
+
+        let err = navigation_failed(&error);
+        assert_eq!(
+            err.to_string(),
+            "Navigation failed: net::ERR_CONNECTION_REFUSED"
+        );
+        assert_eq!(classify(&err), ErrorKind::Other);
+    }
+
+    #[test]
+    fn tracker_recognizes_load_same_document_and_error_pages() {
+        let mut tracker = NavigationTracker::new("main");
+
+        assert_eq!(
+            tracker.observe(&json!({ "method": "Page.loadEventFired", "params": {} })),
+            Some(NavigationEvent::Loaded)
+        );
+        assert_eq!(
+            tracker.observe(&json!({
+                "method": "Page.navigatedWithinDocument",
+                "params": { "frameId": "main", "url": "https://example.com/#top" }
+            })),
+            Some(NavigationEvent::SameDocument)
+        );
+        assert_eq!(
+            tracker.observe(&json!({
+                "method": "Page.frameNavigated",
+                "params": { "frame": { "id": "main", "unreachableUrl": "https://down.test/" } }
+            })),
+            Some(NavigationEvent::Failed(
+                "could not load https://down.test/".to_string()
+            ))
+        );
+        assert_eq!(
+            tracker.observe(&json!({
+                "method": "Page.frameNavigated",
+                "params": { "frame": { "id": "main" }, "type": "BackForwardCacheRestore" }
+            })),
+            Some(NavigationEvent::Loaded)
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde_json::Value;
+use tokio::time::{Duration, Instant};
+
+use crate::cdp::{self, CdpConnection};
+use crate::errors::ErrorKind;
+use crate::output::print_action;
+
+/// How long the request count must stay at or under the threshold to count as idle
+const NETWORK_IDLE_QUIET_MS: u64 = 500;
+const NETWORK_IDLE_TIMEOUT_MS: u64 = 30000;
+
+/// Puppeteer's idle load states and the in-flight requests each tolerates:
+/// `networkidle0` waits for no requests, `networkidle2` allows two long-lived
+/// ones (analytics beacons, long polling, websockets) to stay open.
+pub fn network_idle_threshold(state: &str) -> Option<usize> {
+    match state {
+        "networkidle0" => Some(0),
+        "networkidle2" => Some(2),
+        _ => None,
+    }
+}
+
+/// Update the set of in-flight request ids from one CDP event.
+pub(crate) fn track_request(inflight: &mut HashSet<String>, message: &Value) {
+    let Some(request_id) = message.pointer("/params/requestId").and_then(Value::as_str) else {
+        return;
+    };
+    match message.get("method").and_then(Value::as_str) {
+        Some("Network.requestWillBeSent") => {
+            inflight.insert(request_id.to_string());
+        }
+        Some("Network.loadingFinished" | "Network.loadingFailed") => {
+            inflight.remove(request_id);
+        }
+        _ => {}
+    }
+}
+
+/// Counts in-flight requests on its own session, so it can be started before a
+/// navigation and see every request the navigation makes, even while another
+/// connection drives the page.
+pub struct NetworkIdleWatcher {
+    cdp: CdpConnection,
+    inflight: HashSet<String>,
+}
+
+impl NetworkIdleWatcher {
+    pub async fn start(mut cdp: CdpConnection) -> Result<Self> {
+        cdp.send("Network.enable", serde_json::json!({})).await?;
+        Ok(Self {
+            cdp,
+            inflight: HashSet::new(),
+        })
+    }
+
+    /// Resolve once at most `max_inflight` requests have been open for
+    /// `NETWORK_IDLE_QUIET_MS`. Requests already running when the watcher
+    /// started are not counted.
+    pub async fn wait(&mut self, max_inflight: usize) -> Result<()> {
+        let deadline = Instant::now() + Duration::from_millis(NETWORK_IDLE_TIMEOUT_MS);
+        let quiet = Duration::from_millis(NETWORK_IDLE_QUIET_MS);
+        let mut idle_since = Some(Instant::now());
+        loop {
+            let idle_at = idle_since.map(|since| since + quiet);
+            let message = tokio::select! {
+                message = self.cdp.recv() => message?.context(ErrorKind::Connection.error("Connection closed while waiting for network idle"))?,
+                _ = tokio::time::sleep_until(idle_at.unwrap_or(deadline)), if idle_at.is_some() => return Ok(()),
+                _ = tokio::time::sleep_until(deadline) => {
+                    return Err(ErrorKind::Timeout
+                        .error(format!(
+                            "Timeout: {} requests still in flight after {}ms",
+                            self.inflight.len(),
+                            NETWORK_IDLE_TIMEOUT_MS
+                        ))
+                        .into());
+                }
+            };
+            track_request(&mut self.inflight, &message);
+            idle_since = match (self.inflight.len() <= max_inflight, idle_since) {
+                (true, Some(since)) => Some(since),
+                (true, None) => Some(Instant::now()),
+                (false, _) => None,
+            };
+        }
+    }
+}
+
+/// Canned response for `mock`
+pub struct MockOptions {
+    pub pattern: String,
+    pub status: u16,
+    pub body: String,
+    pub content_type: String,
+    /// Stop after this many milliseconds (default: run until Ctrl-C)
+    pub duration_ms: Option<u64>,
+    pub reload: bool,
+}
+
+pub(crate) fn fulfill_params(request_id: &str, opts: &MockOptions) -> Value {
+    serde_json::json!({
+        "requestId": request_id,
+        "responseCode": opts.status,
+        "responseHeaders": [
+            { "name": "Content-Type", "value": opts.content_type },
+            { "name": "Access-Control-Allow-Origin", "value": "*" },
+        ],
+        "body": base64::engine::general_purpose::STANDARD.encode(&opts.body),
+    })
+}
+
+/// Intercept requests matching `pattern` (`*` wildcards) and answer them with a
+/// canned response. Interception only lasts while this command keeps running.
+pub async fn cmd_mock(port: u16, opts: &MockOptions, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send(
+        "Fetch.enable",
+        serde_json::json!({ "patterns": [{ "urlPattern": opts.pattern, "requestStage": "Request" }] }),
+    )
+    .await?;
+    if opts.reload {
+        cdp.send("Page.reload", serde_json::json!({})).await?;
+    }
+    eprintln!(
+        "Mocking {} with HTTP {} (Ctrl-C to stop)",
+        opts.pattern, opts.status
+    );
+
+    let deadline = opts
+        .duration_ms
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
+    let stop = async {
+        let sleep_until = deadline.unwrap_or_else(|| Instant::now() + Duration::from_secs(86400));
+        tokio::select! {
+            _ = tokio::time::sleep_until(sleep_until) => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    };
+    let fulfilled = fulfill_paused_requests(&mut cdp, opts, json, stop).await?;
+    cdp.send("Fetch.disable", serde_json::json!({})).await?;
+
+    print_action(
+        json,
+        "mock",
+        serde_json::json!({ "pattern": opts.pattern, "fulfilled": fulfilled }),
+        &format!("Mocked {} requests", fulfilled),
+    );
+    Ok(())
+}
+
+/// Answer every `Fetch.requestPaused` with the canned response until `stop`
+/// resolves, printing one line per request. Returns how many were fulfilled.
+async fn fulfill_paused_requests(
+    cdp: &mut CdpConnection,
+    opts: &MockOptions,
+    json: bool,
+    stop: impl std::future::Future<Output = ()>,
+) -> Result<usize> {
+    tokio::pin!(stop);
+    let mut fulfilled = 0usize;
+    loop {
+        let message = tokio::select! {
+            message = cdp.recv() => message?.context(ErrorKind::Connection.error("Connection closed while mocking"))?,
+            _ = &mut stop => break,
+        };
+        if message.get("method").and_then(Value::as_str) != Some("Fetch.requestPaused") {
+            continue;
+        }
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let Some(request_id) = params.get("requestId").and_then(Value::as_str) else {
+            continue;
+        };
+        cdp.send("Fetch.fulfillRequest", fulfill_params(request_id, opts))
+            .await?;
+        fulfilled += 1;
+        let method = params
+            .pointer("/request/method")
+            .and_then(Value::as_str)
+            .unwrap_or("GET");
+        let url = params
+            .pointer("/request/url")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({ "event": "mock", "method": method, "url": url, "status": opts.status })
+            );
+        } else {
+            println!("  {} {} -> {}", method, url, opts.status);
+        }
+    }
+    Ok(fulfilled)
+}
+
+pub async fn cmd_net(port: u16, action: &crate::NetCommand, json: bool) -> Result<()> {
+    match action {
+        crate::NetCommand::WaitResponse {
+            url_pattern,
+            method,
+            status,
+            timeout,
+        } => {
+            let filter = ResponseFilter {
+                pattern: url_pattern.clone(),
+                method: method.clone(),
+                status: *status,
+            };
+            wait_response(port, &filter, *timeout, json).await
+        }
+    }
+}
+
+/// Which response `net wait-response` is waiting for
+pub(crate) struct ResponseFilter {
+    /// `*` wildcards; without any, a substring of the URL
+    pub pattern: String,
+    pub method: Option<String>,
+    pub status: Option<u16>,
+}
+
+impl ResponseFilter {
+    pub(crate) fn matches(&self, url: &str, method: &str, status: u16) -> bool {
+        let url_matches = if self.pattern.contains('*') {
+            crate::snapshot::glob_match(&self.pattern, url)
+        } else {
+            url.contains(&self.pattern)
+        };
+        url_matches
+            && self
+                .method
+                .as_deref()
+                .is_none_or(|m| m.eq_ignore_ascii_case(method))
+            && self.status.is_none_or(|s| s == status)
+    }
+}
+
+async fn wait_response(
+    port: u16,
+    filter: &ResponseFilter,
+    timeout_ms: u64,
+    json: bool,
+) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send("Network.enable", serde_json::json!({})).await?;
+
+    // requestId -> (method, start timestamp in seconds)
+    let mut requests: HashMap<String, (String, f64)> = HashMap::new();
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    loop {
+        let message = tokio::select! {
+            message = cdp.recv() => message?.context(ErrorKind::Connection.error("Connection closed while waiting for a response"))?,
+            _ = tokio::time::sleep_until(deadline) => {
+                return Err(ErrorKind::Timeout
+                    .error(format!(
+                        "Timeout: no response matching '{}' within {}ms",
+                        filter.pattern, timeout_ms
+                    ))
+                    .into());
+            }
+        };
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let request_id = params
+            .get("requestId")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let timestamp = params
+            .get("timestamp")
+            .and_then(Value::as_f64)
+            .unwrap_or_default();
+        match message.get("method").and_then(Value::as_str) {
+            Some("Network.requestWillBeSent") => {
+                let method = params
+                    .pointer("/request/method")
+                    .and_then(Value::as_str)
+                    .unwrap_or("GET");
+                requests.insert(request_id.to_string(), (method.to_string(), timestamp));
+            }
+            Some("Network.responseReceived") => {
+                let url = params
+                    .pointer("/response/url")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let status = params
+                    .pointer("/response/status")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_default() as u16;
+                let (method, started) = requests
+                    .get(request_id)
+                    .map_or(("GET", timestamp), |(m, t)| (m.as_str(), *t));
+                if !filter.matches(url, method, status) {
+                    continue;
+                }
+                let duration_ms = ((timestamp - started) * 1000.0).round();
+                print_action(
+                    json,
+                    "net.wait-response",
+                    serde_json::json!({
+                        "url": url,
+                        "method": method,
+                        "status": status,
+                        "durationMs": duration_ms,
+                    }),
+                    &format!("{} {} -> {} ({}ms)", method, url, status, duration_ms),
+                );
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MockOptions, ResponseFilter, fulfill_params, fulfill_paused_requests,
+        network_idle_threshold, track_request,
+    };
+    use crate::cdp::CdpConnection;
+    use futures::{SinkExt, StreamExt};
+    use std::collections::HashSet;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[test]
+    fn track_request_counts_until_finished_or_failed() {
+        let event = |method: &str, id: &str| serde_json::json!({ "method": method, "params": { "requestId": id } });
+        let mut inflight = HashSet::new();
+        track_request(&mut inflight, &event("Network.requestWillBeSent", "1"));
+        track_request(&mut inflight, &event("Network.requestWillBeSent", "2"));
+        track_request(&mut inflight, &event("Network.responseReceived", "1"));
+        assert_eq!(inflight.len(), 2);
+
+        track_request(&mut inflight, &event("Network.loadingFinished", "1"));
+        track_request(&mut inflight, &event("Network.loadingFailed", "2"));
+        assert!(inflight.is_empty());
+    }
+
+    #[test]
+    fn network_idle_thresholds_follow_puppeteer() {
+        assert_eq!(network_idle_threshold("networkidle0"), Some(0));
+        assert_eq!(network_idle_threshold("networkidle2"), Some(2));
+        assert_eq!(network_idle_threshold("load"), None);
+    }
+
+    #[test]
+    fn response_filter_matches_pattern_method_and_status() {
+        let filter = ResponseFilter {
+            pattern: "*/api/users*".to_string(),
+            method: Some("post".to_string()),
+            status: Some(201),
+        };
+        assert!(filter.matches("https://x.test/api/users?page=2", "POST", 201));
+        assert!(!filter.matches("https://x.test/api/users", "GET", 201));
+        assert!(!filter.matches("https://x.test/api/users", "POST", 500));
+        assert!(!filter.matches("https://x.test/api/teams", "POST", 201));
+
+        let substring = ResponseFilter {
+            pattern: "/graphql".to_string(),
+            method: None,
+            status: None,
+        };
+        assert!(substring.matches("https://x.test/graphql?op=Feed", "POST", 200));
+    }
+
+    #[test]
+    fn fulfill_params_encode_body_and_headers() {
+        let opts = MockOptions {
+            pattern: "*/api/*".to_string(),
+            status: 201,
+            body: "{\"ok\":true}".to_string(),
+            content_type: "application/json".to_string(),
+            duration_ms: None,
+            reload: false,
+        };
+        let params = fulfill_params("req-1", &opts);
+
+        assert_eq!(params["requestId"], "req-1");
+        assert_eq!(params["responseCode"], 201);
+        assert_eq!(params["body"], "eyJvayI6dHJ1ZX0=");
+        assert_eq!(params["responseHeaders"][0]["value"], "application/json");
+    }
+
+    #[tokio::test]
+    async fn mock_fulfills_requests_paused_back_to_back() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            // Both events go out before the first fulfillRequest is answered, so
+            // the second one arrives while the client waits for that reply.
+            for id in ["r1", "r2"] {
+                let paused = serde_json::json!({
+                    "method": "Fetch.requestPaused",
+                    "params": { "requestId": id, "request": { "method": "GET", "url": "https://x.test/api" } }
+                });
+                ws.send(Message::Text(paused.to_string())).await.unwrap();
+            }
+            let mut fulfilled = Vec::new();
+            while fulfilled.len() < 2 {
+                let Some(Ok(Message::Text(text))) = ws.next().await else {
+                    break;
+                };
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                fulfilled.push(request["params"]["requestId"].as_str().unwrap().to_string());
+                let reply = serde_json::json!({ "id": request["id"], "result": {} });
+                ws.send(Message::Text(reply.to_string())).await.unwrap();
+            }
+            let _ = done_tx.send(());
+            // Keep the socket open until the client has stopped.
+            let _ = ws.next().await;
+            fulfilled
+        });
+
+        let mut cdp = CdpConnection::connect(&format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let opts = MockOptions {
+            pattern: "*/api*".to_string(),
+            status: 200,
+            body: String::new(),
+            content_type: "text/plain".to_string(),
+            duration_ms: None,
+            reload: false,
+        };
+        let stop = async {
+            let _ = done_rx.await;
+        };
+        let count = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            fulfill_paused_requests(&mut cdp, &opts, false, stop),
+        )
+        .await
+        .expect("second paused request was never fulfilled")
+        .unwrap();
+        drop(cdp);
+
+        assert_eq!(count, 2);
+        assert_eq!(server.await.unwrap(), ["r1", "r2"]);
+    }
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How command results are written to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// Same as `--json`
+    Json,
+    /// Like `json`, but lists are written one compact value per line
+    Ndjson,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Set the format once at startup from `--format`; later calls are ignored.
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Print a list result for `--json` callers: a pretty array, or one value per
+/// line under `--format ndjson`.
+pub fn print_list<T: Serialize>(items: &[T]) -> Result<()> {
+    println!("{}", list_json(items, format())?);
+    Ok(())
+}
+
+pub(crate) fn list_json<T: Serialize>(items: &[T], format: OutputFormat) -> Result<String> {
+    if format != OutputFormat::Ndjson {
+        return Ok(serde_json::to_string_pretty(items)?);
+    }
+    let lines = items
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Report a completed mutating command: a JSON object with `action` and `ok: true`
+/// (plus `details`) under `--json`, otherwise a `✓ message` line.
+pub fn print_action(json: bool, action: &str, details: Value, message: &str) {
+    if !json {
+        println!("✓ {}", message);
+        return;
+    }
+    println!("{}", action_json(action, details));
+}
+
+pub(crate) fn action_json(action: &str, details: Value) -> Value {
+    let mut out = serde_json::json!({ "action": action, "ok": true });
+    if let Value::Object(details) = details {
+        for (key, value) in details {
+            out[key] = value;
+        }
+    }
+    out
+}
+
+/// Report a failed command as `{"ok": false, "error": {"kind", "message"}}` for
+/// `--json` callers.
+pub fn print_error_json(err: &anyhow::Error, kind: crate::errors::ErrorKind) {
+    println!("{}", crate::errors::error_json(err, kind));
+}
+
+/// Resolve an output path before writing: expand `{timestamp}` (Unix milliseconds)
+/// so repeated captures don't overwrite each other, and create missing parent
+/// directories.
+pub fn prepare_output_path(path: impl AsRef<Path>) -> Result<PathBuf> {
+    let path = expand_timestamp(path.as_ref(), now_millis());
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    Ok(path)
+}
+
+pub fn now_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+pub fn expand_timestamp(path: &Path, millis: u128) -> PathBuf {
+    PathBuf::from(
+        path.to_string_lossy()
+            .replace("{timestamp}", &millis.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputFormat, action_json, expand_timestamp, list_json};
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn action_json_merges_details() {
+        let out = action_json("click", json!({ "selector": "#go" }));
+
+        assert_eq!(
+            out,
+            json!({ "action": "click", "ok": true, "selector": "#go" })
+        );
+    }
+
+    #[test]
+    fn list_json_writes_one_value_per_line_for_ndjson() {
+        let items = [json!({ "id": "a" }), json!({ "id": "b" })];
+
+        assert_eq!(
+            list_json(&items, OutputFormat::Ndjson).unwrap(),
+            "{\"id\":\"a\"}\n{\"id\":\"b\"}"
+        );
+        assert!(
+            list_json(&items, OutputFormat::Json)
+                .unwrap()
+                .starts_with("[\n")
+        );
+    }
+
+    #[test]
+    fn expand_timestamp_replaces_every_placeholder() {
+        let path = expand_timestamp(Path::new("/tmp/shots/{timestamp}/page-{timestamp}.jpg"), 42);
+
+        assert_eq!(path, Path::new("/tmp/shots/42/page-42.jpg"));
+        assert_eq!(
+            expand_timestamp(Path::new("out.jpg"), 42),
+            Path::new("out.jpg")
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{Result, anyhow};
+
+use crate::cdp;
+use crate::output::print_action;
+
+/// Friendly permission names mapped to CDP `Browser.PermissionType` values
+const PERMISSION_NAMES: &[(&str, &str)] = &[
+    ("background-sync", "backgroundSync"),
+    ("camera", "videoCapture"),
+    ("clipboard-read", "clipboardReadWrite"),
+    ("clipboard-write", "clipboardSanitizedWrite"),
+    ("display-capture", "displayCapture"),
+    ("geolocation", "geolocation"),
+    ("idle-detection", "idleDetection"),
+    ("local-fonts", "localFonts"),
+    ("microphone", "audioCapture"),
+    ("midi", "midi"),
+    ("midi-sysex", "midiSysex"),
+    ("notifications", "notifications"),
+    ("payment-handler", "paymentHandler"),
+    ("screen-wake-lock", "wakeLockScreen"),
+    ("sensors", "sensors"),
+    ("storage-access", "storageAccess"),
+    ("window-management", "windowManagement"),
+];
+
+pub(crate) fn cdp_permission_names(names: &[String]) -> Result<Vec<&'static str>> {
+    names
+        .iter()
+        .map(|name| {
+            PERMISSION_NAMES
+                .iter()
+                .find(|(friendly, _)| friendly.eq_ignore_ascii_case(name))
+                .map(|(_, cdp_name)| *cdp_name)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = PERMISSION_NAMES.iter().map(|(f, _)| *f).collect();
+                    anyhow!(
+                        "Unknown permission '{}'. Known permissions: {}",
+                        name,
+                        known.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+pub async fn cmd_permissions(
+    port: u16,
+    action: &crate::PermissionsCommand,
+    json: bool,
+) -> Result<()> {
+    let mut cdp = cdp::connect_browser(port).await?;
+    match action {
+        crate::PermissionsCommand::Grant { names, origin } => {
+            let permissions = cdp_permission_names(names)?;
+            let mut params = serde_json::json!({ "permissions": permissions });
+            if let Some(origin) = origin {
+                params["origin"] = serde_json::json!(origin);
+            }
+            cdp.send("Browser.grantPermissions", params).await?;
+            print_action(
+                json,
+                "permissions.grant",
+                serde_json::json!({ "permissions": names, "origin": origin }),
+                &format!("Granted {}", names.join(", ")),
+            );
+        }
+        crate::PermissionsCommand::Reset => {
+            cdp.send("Browser.resetPermissions", serde_json::json!({}))
+                .await?;
+            print_action(
+                json,
+                "permissions.reset",
+                serde_json::json!({}),
+                "Permissions reset",
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cdp_permission_names;
+
+    #[test]
+    fn maps_friendly_permission_names() {
+        let names = vec!["clipboard-read".to_string(), "Notifications".to_string()];
+
+        let mapped = cdp_permission_names(&names).expect("known permissions");
+
+        assert_eq!(mapped, vec!["clipboardReadWrite", "notifications"]);
+    }
+
+    #[test]
+    fn rejects_unknown_permission_with_known_list() {
+        let err = cdp_permission_names(&["teleport".to_string()]).unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains("Unknown permission 'teleport'"));
+        assert!(message.contains("geolocation"));
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde_json::Value;
+use std::path::Path;
+use tokio::time::{Duration, Instant, timeout};
+
+use crate::cdp;
+use crate::errors::ErrorKind;
+use crate::output::print_action;
+
+/// Whether a frame at `timestamp` (seconds) should be kept given the last kept
+/// frame's timestamp and the target frame rate.
+pub(crate) fn keep_frame(last_kept: Option<f64>, timestamp: f64, fps: u32) -> bool {
+    match last_kept {
+        None => true,
+        Some(last) => timestamp - last >= 1.0 / fps as f64,
+    }
+}
+
+/// Record a screencast for `duration_ms`, writing JPEG frames to `dir`.
+pub async fn cmd_record(
+    port: u16,
+    dir: &Path,
+    duration_ms: u64,
+    fps: u32,
+    json: bool,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create recording directory {}", dir.display()))?;
+
+    let mut cdp = cdp::connect_active(port).await?;
+    cdp.send(
+        "Page.startScreencast",
+        serde_json::json!({ "format": "jpeg", "quality": 60 }),
+    )
+    .await?;
+
+    let deadline = Instant::now() + Duration::from_millis(duration_ms);
+    let mut last_kept: Option<f64> = None;
+    let mut frames = 0usize;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let message = match timeout(remaining, cdp.recv()).await {
+            Ok(message) => message?
+                .context(ErrorKind::Connection.error("Connection closed while recording"))?,
+            Err(_) => break,
+        };
+        if message.get("method").and_then(Value::as_str) != Some("Page.screencastFrame") {
+            continue;
+        }
+        let params = message.get("params").unwrap_or(&Value::Null);
+        // Chrome sends the next frame only after this one is acknowledged.
+        if let Some(session_id) = params.get("sessionId") {
+            cdp.send(
+                "Page.screencastFrameAck",
+                serde_json::json!({ "sessionId": session_id }),
+            )
+            .await?;
+        }
+        let timestamp = params
+            .pointer("/metadata/timestamp")
+            .and_then(Value::as_f64)
+            .unwrap_or_default();
+        if !keep_frame(last_kept, timestamp, fps) {
+            continue;
+        }
+        let Some(data) = params.get("data").and_then(Value::as_str) else {
+            continue;
+        };
+        let bytes = base64::engine::general_purpose::STANDARD.decode(data)?;
+        frames += 1;
+        std::fs::write(dir.join(format!("frame-{:05}.jpg", frames)), bytes)?;
+        last_kept = Some(timestamp);
+    }
+    cdp.send("Page.stopScreencast", serde_json::json!({}))
+        .await?;
+
+    print_action(
+        json,
+        "record",
+        serde_json::json!({ "dir": dir, "frames": frames, "fps": fps }),
+        &format!("Recorded {} frames to {}", frames, dir.display()),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keep_frame;
+
+    #[test]
+    fn keep_frame_throttles_to_fps() {
+        assert!(keep_frame(None, 10.0, 5));
+        assert!(!keep_frame(Some(10.0), 10.1, 5));
+        assert!(keep_frame(Some(10.0), 10.25, 5));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use serde_json::Value;
+
+use crate::cdp::{self, CdpConnection};
+
+/// Refs from the last `snapshot --interactive` of one target, mapped to DOM
+/// backend node ids (stable for the lifetime of the document).
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct RefMap {
+    pub url: String,
+    pub refs: BTreeMap<String, i64>,
+}
+
+impl RefMap {
+    /// Backend node id for `ref_id`, or why it can no longer be used.
+    pub(crate) fn lookup(&self, ref_id: &str, current_url: &str) -> Result<i64> {
+        if self.url != current_url {
+            return Err(anyhow!(
+                "Ref {} is stale: the page changed from {} since the snapshot; take a new snapshot --interactive",
+                ref_id,
+                self.url
+            ));
+        }
+        self.refs.get(ref_id).copied().ok_or_else(|| {
+            anyhow!(
+                "Ref not found: {} (take a snapshot --interactive first)",
+                ref_id
+            )
+        })
+    }
+}
+
+/// Tags the element with its ref, or returns where it lives when a document
+/// selector could not reach it.
+const TAG_REF_FUNCTION: &str = r#"function(ref) {
+    if (window !== window.top) return 'an iframe';
+    if (this.getRootNode() !== document) return 'a shadow root';
+    for (const el of document.querySelectorAll(`[data-ab-ref="${CSS.escape(ref)}"]`)) {
+        if (el !== this) el.removeAttribute('data-ab-ref');
+    }
+    this.setAttribute('data-ab-ref', ref);
+    return '';
+}"#;
+
+fn refs_file(target_id: &str) -> PathBuf {
+    PathBuf::from(format!("/tmp/browser-cli-refs-{}.json", target_id))
+}
+
+/// Target id and URL of the page `cdp` is attached to.
+async fn current_target(cdp: &mut CdpConnection) -> Result<(String, String)> {
+    let info = cdp
+        .send("Target.getTargetInfo", serde_json::json!({}))
+        .await?;
+    let field = |key: &str| {
+        info.pointer(&format!("/targetInfo/{}", key))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .with_context(|| format!("Target.getTargetInfo returned no {}", key))
+    };
+    Ok((field("targetId")?, field("url")?))
+}
+
+pub(crate) async fn save_refs(cdp: &mut CdpConnection, refs: BTreeMap<String, i64>) -> Result<()> {
+    let (target_id, url) = current_target(cdp).await?;
+    let path = refs_file(&target_id);
+    std::fs::write(&path, serde_json::to_string(&RefMap { url, refs })?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Refs the React fiber walker tagged as `data-ab-ref` attributes, read from a
+/// `DOM.getDocument` tree (including shadow roots and frame documents).
+pub(crate) fn tagged_refs(node: &Value, refs: &mut BTreeMap<String, i64>) {
+    let attributes = node
+        .get("attributes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for pair in attributes.chunks(2) {
+        if let [name, ref_id] = pair
+            && name == "data-ab-ref"
+            && let (Some(ref_id), Some(backend_id)) = (
+                ref_id.as_str(),
+                node.get("backendNodeId").and_then(Value::as_i64),
+            )
+        {
+            refs.insert(ref_id.to_string(), backend_id);
+        }
+    }
+    for key in ["children", "shadowRoots"] {
+        for child in node
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            tagged_refs(child, refs);
+        }
+    }
+    if let Some(document) = node.get("contentDocument") {
+        tagged_refs(document, refs);
+    }
+}
+
+/// Save the refs a React snapshot just tagged, so `--ref` finds them even after
+/// another snapshot reuses the same ids.
+pub(crate) async fn save_tagged_refs(cdp: &mut CdpConnection) -> Result<()> {
+    let document = cdp
+        .send(
+            "DOM.getDocument",
+            serde_json::json!({ "depth": -1, "pierce": true }),
+        )
+        .await?;
+    let mut refs = BTreeMap::new();
+    if let Some(root) = document.get("root") {
+        tagged_refs(root, &mut refs);
+    }
+    save_refs(cdp, refs).await
+}
+
+/// Resolve a snapshot ref to a selector usable by every command: the element is
+/// tagged with a `data-ab-ref` attribute (as React snapshots do) and matched by
+/// it. The tag is first removed from any other element in the document still
+/// carrying it from an earlier snapshot, so the selector matches only the ref's
+/// own element. Refs inside shadow roots or iframes are rejected, since a
+/// document selector can't reach them.
+pub async fn resolve_ref(port: u16, ref_id: &str) -> Result<String> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let (target_id, url) = current_target(&mut cdp).await?;
+    let map: RefMap = std::fs::read_to_string(refs_file(&target_id))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .with_context(|| {
+            format!(
+                "Ref not found: {} (take a snapshot --interactive first)",
+                ref_id
+            )
+        })?;
+    let backend_id = map.lookup(ref_id, &url)?;
+    let resolved = cdp
+        .send(
+            "DOM.resolveNode",
+            serde_json::json!({ "backendNodeId": backend_id }),
+        )
+        .await
+        .with_context(|| format!("Element for ref {} not found; take a new snapshot", ref_id))?;
+    let object_id = resolved
+        .pointer("/object/objectId")
+        .and_then(|id| id.as_str())
+        .with_context(|| format!("Element for ref {} not found", ref_id))?;
+    let tagged = cdp
+        .send(
+            "Runtime.callFunctionOn",
+            serde_json::json!({
+                "objectId": object_id,
+                "functionDeclaration": TAG_REF_FUNCTION,
+                "arguments": [{ "value": ref_id }],
+                "returnByValue": true,
+            }),
+        )
+        .await?;
+    ref_selector(ref_id, &tagged)
+}
+
+/// The selector for a ref `TAG_REF_FUNCTION` tagged, given its reply.
+fn ref_selector(ref_id: &str, tagged: &Value) -> Result<String> {
+    match tagged.pointer("/result/value").and_then(Value::as_str) {
+        Some("") => Ok(format!("css=[data-ab-ref=\"{}\"]", ref_id)),
+        Some(location) => Err(anyhow!(
+            "Ref {} is inside {}, which --ref can't reach; use a selector instead",
+            ref_id,
+            location
+        )),
+        None => Err(anyhow!("Could not tag the element for ref {}", ref_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RefMap, ref_selector, tagged_refs};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn lookup_rejects_stale_and_unknown_refs() {
+        let map = RefMap {
+            url: "https://shop.test/cart".to_string(),
+            refs: [("e1".to_string(), 42)].into_iter().collect(),
+        };
+        assert_eq!(map.lookup("e1", "https://shop.test/cart").unwrap(), 42);
+        assert!(
+            map.lookup("e2", "https://shop.test/cart")
+                .unwrap_err()
+                .to_string()
+                .contains("Ref not found")
+        );
+        assert!(
+            map.lookup("e1", "https://shop.test/checkout")
+                .unwrap_err()
+                .to_string()
+                .contains("stale")
+        );
+    }
+
+    #[test]
+    fn tagged_refs_walk_children_shadow_roots_and_frames() {
+        let document = serde_json::json!({
+            "backendNodeId": 1,
+            "children": [
+                { "backendNodeId": 2, "attributes": ["class", "nav", "data-ab-ref", "e1"] },
+                {
+                    "backendNodeId": 3,
+                    "attributes": ["id", "host"],
+                    "shadowRoots": [{ "backendNodeId": 4, "children": [
+                        { "backendNodeId": 5, "attributes": ["data-ab-ref", "e2"] }
+                    ]}]
+                },
+                {
+                    "backendNodeId": 6,
+                    "contentDocument": { "backendNodeId": 7, "children": [
+                        { "backendNodeId": 8, "attributes": ["data-ab-ref", "e3"] }
+                    ]}
+                }
+            ]
+        });
+        let mut refs = BTreeMap::new();
+        tagged_refs(&document, &mut refs);
+
+        let expected: BTreeMap<String, i64> = [("e1", 2), ("e2", 5), ("e3", 8)]
+            .into_iter()
+            .map(|(id, backend)| (id.to_string(), backend))
+            .collect();
+        assert_eq!(refs, expected);
+    }
+
+    #[test]
+    fn refs_outside_the_document_tree_are_rejected() {
+        let tagged = serde_json::json!({ "result": { "type": "string", "value": "" } });
+        assert_eq!(
+            ref_selector("e1", &tagged).unwrap(),
+            r#"css=[data-ab-ref="e1"]"#
+        );
+
+        let in_shadow =
+            serde_json::json!({ "result": { "type": "string", "value": "a shadow root" } });
+        let err = ref_selector("e2", &in_shadow).unwrap_err().to_string();
+        assert!(err.contains("Ref e2 is inside a shadow root"), "{}", err);
+
+        let in_frame = serde_json::json!({ "result": { "type": "string", "value": "an iframe" } });
+        let err = ref_selector("e3", &in_frame).unwrap_err().to_string();
+        assert!(err.contains("Ref e3 is inside an iframe"), "{}", err);
+    }
+}
@@ -1,16 +1,102 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{Duration, Instant, timeout};
 
 use crate::cdp;
 
+/// Filters applied to captured console events
+#[derive(Default)]
+pub(crate) struct ConsoleFilter {
+    pub(crate) levels: Vec<String>,
+    pub(crate) grep: Option<String>,
+    /// Epoch milliseconds; older events are dropped
+    pub(crate) since_ms: Option<f64>,
+}
+
+impl ConsoleFilter {
+    pub(crate) fn matches(&self, event: &Value) -> bool {
+        let level = event.get("type").and_then(Value::as_str).unwrap_or("log");
+        if !self.levels.is_empty() && !self.levels.iter().any(|l| l == level) {
+            return false;
+        }
+        if let Some(grep) = &self.grep {
+            let text = event.get("text").and_then(Value::as_str).unwrap_or("");
+            if !text.contains(grep.as_str()) {
+                return false;
+            }
+        }
+        if let (Some(since), Some(ts)) = (
+            self.since_ms,
+            event.get("timestamp").and_then(Value::as_f64),
+        ) && ts < since
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// CDP reports `console.warn` as "warning"; accept the shorter spelling too.
+fn normalize_level(level: &str) -> String {
+    match level.trim() {
+        "warn" => "warning".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as f64)
+        .unwrap_or(0.0)
+}
+
 pub async fn cmd_runtime(port: u16, action: &crate::RuntimeCommand, json: bool) -> Result<()> {
-    let (kind, reload, wait_ms) = match action {
-        crate::RuntimeCommand::Console { reload, wait_ms } => ("console", *reload, *wait_ms),
-        crate::RuntimeCommand::Exceptions { reload, wait_ms } => ("exceptions", *reload, *wait_ms),
-    };
-    let events = collect_runtime_events(port, kind, reload, wait_ms).await?;
-    print_runtime_events(kind, &events, json)?;
+    match action {
+        crate::RuntimeCommand::Console {
+            reload,
+            wait_ms,
+            level,
+            grep,
+            since,
+            out,
+        } => {
+            let filter = ConsoleFilter {
+                levels: level.iter().map(|l| normalize_level(l)).collect(),
+                grep: grep.clone(),
+                since_ms: since.map(|ms| now_ms() - ms as f64),
+            };
+            let events = collect_runtime_events(port, "console", *reload, *wait_ms).await?;
+            let events: Vec<Value> = events.into_iter().filter(|e| filter.matches(e)).collect();
+            if let Some(out) = out {
+                let out = crate::output::prepare_output_path(out)?;
+                write_events_jsonl(&out, &events)?;
+                crate::output::print_action(
+                    json,
+                    "runtime.console",
+                    serde_json::json!({ "out": out, "events": events.len() }),
+                    &format!("Wrote {} events to {}", events.len(), out.display()),
+                );
+                return Ok(());
+            }
+            print_runtime_events("console", &events, json)
+        }
+        crate::RuntimeCommand::Exceptions { reload, wait_ms } => {
+            let events = collect_runtime_events(port, "exceptions", *reload, *wait_ms).await?;
+            print_runtime_events("exceptions", &events, json)
+        }
+    }
+}
+
+fn write_events_jsonl(path: &Path, events: &[Value]) -> Result<()> {
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    for event in events {
+        writeln!(file, "{}", event)?;
+    }
     Ok(())
 }
 
@@ -62,6 +148,7 @@ fn format_console_event(params: &Value) -> Value {
         "args": args,
     });
     add_stack_location(&mut event, params);
+    add_timestamp(&mut event, params);
     event
 }
 
@@ -76,9 +163,16 @@ fn format_exception_event(params: &Value) -> Value {
         "columnNumber": details.get("columnNumber").and_then(Value::as_i64).unwrap_or(0),
     });
     add_stack_location(&mut event, details);
+    add_timestamp(&mut event, params);
     event
 }
 
+fn add_timestamp(event: &mut Value, params: &Value) {
+    if let Some(ts) = params.get("timestamp").and_then(Value::as_f64) {
+        event["timestamp"] = serde_json::json!(ts.round() as i64);
+    }
+}
+
 fn remote_object_text(value: &Value) -> String {
     if let Some(text) = value.get("value").and_then(Value::as_str) {
         return text.to_string();
@@ -120,8 +214,7 @@ fn add_stack_location(event: &mut Value, params: &Value) {
 
 fn print_runtime_events(kind: &str, events: &[Value], json: bool) -> Result<()> {
     if json {
-        println!("{}", serde_json::to_string_pretty(events)?);
-        return Ok(());
+        return crate::output::print_list(events);
     }
     for event in events {
         let text = event
@@ -130,14 +223,17 @@ fn print_runtime_events(kind: &str, events: &[Value], json: bool) -> Result<()>
             .and_then(Value::as_str)
             .unwrap_or("");
         let label = event.get("type").and_then(Value::as_str).unwrap_or(kind);
-        println!("[{label}] {text}");
+        match event.get("timestamp").and_then(Value::as_i64) {
+            Some(ts) => println!("{ts} [{label}] {text}"),
+            None => println!("[{label}] {text}"),
+        }
     }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::format_runtime_event;
+    use super::{ConsoleFilter, format_runtime_event};
     use serde_json::json;
 
     #[test]
@@ -216,4 +312,38 @@ mod tests {
 
         assert_eq!(format_runtime_event("exceptions", &message), None);
     }
+
+    #[test]
+    fn keeps_timestamps_from_cdp_events() {
+        let message = json!({
+            "method": "Runtime.consoleAPICalled",
+            "params": {
+                "type": "log",
+                "args": [{ "type": "string", "value": "hi" }],
+                "timestamp": 1700000000123.4
+            }
+        });
+
+        let event = format_runtime_event("console", &message).expect("console event");
+
+        assert_eq!(event["timestamp"], json!(1700000000123_i64));
+    }
+
+    #[test]
+    fn console_filter_matches_level_grep_and_since() {
+        let event = json!({ "type": "warning", "text": "disk almost full", "timestamp": 2000 });
+        let filter = |levels: &[&str], grep: Option<&str>, since_ms: Option<f64>| ConsoleFilter {
+            levels: levels.iter().map(|l| l.to_string()).collect(),
+            grep: grep.map(String::from),
+            since_ms,
+        };
+
+        assert!(ConsoleFilter::default().matches(&event));
+        assert!(filter(&["error", "warning"], None, None).matches(&event));
+        assert!(!filter(&["error"], None, None).matches(&event));
+        assert!(filter(&[], Some("almost"), None).matches(&event));
+        assert!(!filter(&[], Some("empty"), None).matches(&event));
+        assert!(filter(&[], None, Some(1000.0)).matches(&event));
+        assert!(!filter(&[], None, Some(3000.0)).matches(&event));
+    }
 }
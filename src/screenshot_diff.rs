@@ -0,0 +1,180 @@
+use anyhow::{Context, Result, anyhow};
+use image::{ImageFormat, ImageReader, Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Per-channel difference (0-255) below which pixels count as unchanged, so
+/// anti-aliasing noise does not register as a regression. Far too small for
+/// JPEG artifacts, which is why baselines must be PNG.
+const CHANNEL_TOLERANCE: u8 = 16;
+
+/// Baseline comparison settings for `screenshot`
+pub struct DiffOptions {
+    pub baseline: PathBuf,
+    pub diff_out: Option<PathBuf>,
+    /// Largest changed-pixel ratio (0-1) that still passes
+    pub threshold: f64,
+}
+
+/// Outcome of comparing a screenshot against its baseline
+#[derive(Debug)]
+pub struct DiffReport {
+    pub changed: u64,
+    pub total: u64,
+}
+
+impl DiffReport {
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.changed as f64 / self.total as f64
+        }
+    }
+}
+
+/// Whether `path` names a PNG file, which screenshots then capture losslessly.
+pub(crate) fn is_png(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+}
+
+/// Count pixels that changed between two images of the same size and render
+/// the diff: changed pixels in red over a faded copy of the current image.
+pub(crate) fn diff_images(
+    baseline: &RgbaImage,
+    current: &RgbaImage,
+) -> Result<(DiffReport, RgbaImage)> {
+    if baseline.dimensions() != current.dimensions() {
+        return Err(anyhow!(
+            "Size mismatch: baseline {}x{}, current {}x{}",
+            baseline.width(),
+            baseline.height(),
+            current.width(),
+            current.height()
+        ));
+    }
+    let mut changed = 0;
+    let diff = RgbaImage::from_fn(current.width(), current.height(), |x, y| {
+        let a = baseline.get_pixel(x, y);
+        let b = current.get_pixel(x, y);
+        let delta = (0..3).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0);
+        if delta > CHANNEL_TOLERANCE {
+            changed += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let mean = (b[0] as u32 + b[1] as u32 + b[2] as u32) / 3;
+            let gray = (180 + mean / 4) as u8;
+            Rgba([gray, gray, gray, 255])
+        }
+    });
+    let report = DiffReport {
+        changed,
+        total: current.width() as u64 * current.height() as u64,
+    };
+    Ok((report, diff))
+}
+
+/// Compare `current` image bytes against the baseline, writing the diff image if requested.
+pub fn compare(current: &[u8], opts: &DiffOptions) -> Result<DiffReport> {
+    let read_error = || format!("Failed to read baseline {}", opts.baseline.display());
+    // Go by content, not extension: older captures saved JPEG data under any name.
+    let reader = ImageReader::open(&opts.baseline)
+        .with_context(read_error)?
+        .with_guessed_format()
+        .with_context(read_error)?;
+    if reader.format() != Some(ImageFormat::Png) {
+        return Err(anyhow!(
+            "Baseline {} is not a PNG; lossy baselines fail the comparison, so save one with `screenshot base.png`",
+            opts.baseline.display()
+        ));
+    }
+    let baseline = reader.decode().with_context(read_error)?.to_rgba8();
+    let current = image::load_from_memory(current)
+        .context("Failed to decode screenshot")?
+        .to_rgba8();
+    let (report, diff) = diff_images(&baseline, &current)?;
+    if let Some(path) = &opts.diff_out {
+        diff.save_with_format(path, ImageFormat::Png)
+            .with_context(|| format!("Failed to write diff image {}", path.display()))?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiffReport, diff_images, is_png};
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::path::Path;
+
+    #[test]
+    fn diff_ratio_handles_empty_images() {
+        assert_eq!(
+            DiffReport {
+                changed: 0,
+                total: 0
+            }
+            .ratio(),
+            0.0
+        );
+        assert_eq!(
+            DiffReport {
+                changed: 25,
+                total: 100
+            }
+            .ratio(),
+            0.25
+        );
+    }
+
+    #[test]
+    fn diff_marks_changed_pixels_and_ignores_small_noise() {
+        let baseline = RgbaImage::from_pixel(4, 2, Rgba([100, 100, 100, 255]));
+        let mut current = baseline.clone();
+        current.put_pixel(0, 0, Rgba([110, 95, 100, 255]));
+        current.put_pixel(3, 1, Rgba([200, 100, 100, 255]));
+
+        let (report, diff) = diff_images(&baseline, &current).unwrap();
+
+        assert_eq!(report.changed, 1);
+        assert_eq!(report.total, 8);
+        assert_eq!(diff.get_pixel(3, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(diff.get_pixel(1, 0), &Rgba([205, 205, 205, 255]));
+    }
+
+    #[test]
+    fn diff_rejects_size_mismatch() {
+        let baseline = RgbaImage::new(4, 2);
+        let current = RgbaImage::new(2, 4);
+
+        let err = diff_images(&baseline, &current).unwrap_err().to_string();
+        assert!(err.contains("baseline 4x2, current 2x4"), "{}", err);
+    }
+
+    #[test]
+    fn jpeg_round_trip_exceeds_tolerance() {
+        // A detailed image (checkerboard over a gradient) saved the way plain
+        // screenshots are: JPEG at quality 15.
+        let original = RgbaImage::from_fn(64, 64, |x, y| {
+            let shade = if (x / 4 + y / 4) % 2 == 0 { 40 } else { 220 };
+            Rgba([shade, (x * 4) as u8, (y * 4) as u8, 255])
+        });
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 15)
+            .encode_image(&image::DynamicImage::ImageRgba8(original.clone()).to_rgb8())
+            .unwrap();
+        let round_trip = image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg)
+            .unwrap()
+            .to_rgba8();
+
+        let (report, _) = diff_images(&original, &round_trip).unwrap();
+        assert!(report.ratio() > 0.1, "{:?}", report);
+    }
+
+    #[test]
+    fn png_extension_is_case_insensitive() {
+        assert!(is_png(Path::new("shots/base.png")));
+        assert!(is_png(Path::new("BASE.PNG")));
+        assert!(!is_png(Path::new("/tmp/claude/screenshot.jpg")));
+        assert!(!is_png(Path::new("screenshot")));
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::{Result, anyhow};
+
+use crate::cdp;
+use crate::commands::{element_expr, eval_promise};
+use crate::output::print_action;
+
+/// Vertical alignment passed to `scrollIntoView({ block })`
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ScrollAlign {
+    Start,
+    Center,
+    End,
+}
+
+impl ScrollAlign {
+    fn block(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Center => "center",
+            Self::End => "end",
+        }
+    }
+}
+
+/// Scrolls, then resolves once the window and target positions have held still
+/// for a few frames so smooth scrolling has finished.
+const SCROLL_SCRIPT_TEMPLATE: &str = r#"new Promise((resolve, reject) => {
+    const el = __ELEMENT__;
+    if (__NEEDS_ELEMENT__ && !el) throw new Error('Element not found');
+    __ACTION__;
+    const deadline = Date.now() + __TIMEOUT__;
+    const read = () => ({
+        x: window.scrollX,
+        y: window.scrollY,
+        top: el ? el.scrollTop : null,
+        rect: el ? el.getBoundingClientRect().top : null,
+    });
+    let last = read();
+    let stable = 0;
+    const check = () => {
+        const now = read();
+        const same = now.x === last.x && now.y === last.y && now.top === last.top && now.rect === last.rect;
+        stable = same ? stable + 1 : 0;
+        last = now;
+        if (stable >= 3) resolve(now);
+        else if (Date.now() > deadline) reject(new Error('Timeout: scrolling did not settle'));
+        else requestAnimationFrame(check);
+    };
+    requestAnimationFrame(check);
+})"#;
+
+/// Target and amount for `cmd_scroll`
+pub struct ScrollOptions {
+    pub selector: Option<String>,
+    pub into_view: bool,
+    pub align: ScrollAlign,
+    pub by: Option<f64>,
+    pub timeout_ms: u64,
+}
+
+fn build_scroll_script(opts: &ScrollOptions) -> Result<String> {
+    let element = match opts.selector.as_deref() {
+        Some(selector) => element_expr(selector, None)?,
+        None => "null".to_string(),
+    };
+    let action = match (opts.into_view, opts.by) {
+        (true, _) => format!(
+            "el.scrollIntoView({{ block: '{}', inline: 'nearest' }})",
+            opts.align.block()
+        ),
+        (false, Some(dy)) if opts.selector.is_some() => format!("el.scrollBy(0, {})", dy),
+        (false, Some(dy)) => format!("window.scrollBy(0, {})", dy),
+        (false, None) => return Err(anyhow!("Give --into-view or --by <pixels>")),
+    };
+    Ok(SCROLL_SCRIPT_TEMPLATE
+        .replace("__ELEMENT__", &element)
+        .replace("__NEEDS_ELEMENT__", &opts.selector.is_some().to_string())
+        .replace("__ACTION__", &action)
+        .replace("__TIMEOUT__", &opts.timeout_ms.to_string()))
+}
+
+pub async fn cmd_scroll(port: u16, opts: &ScrollOptions, json: bool) -> Result<()> {
+    let script = build_scroll_script(opts)?;
+    let mut cdp = cdp::connect_active(port).await?;
+    if let Some(selector) = &opts.selector {
+        crate::commands::await_element(&mut cdp, selector, None).await?;
+    }
+    let position = eval_promise(&mut cdp, &script).await?;
+    let x = position.get("x").and_then(|x| x.as_f64()).unwrap_or(0.0);
+    let y = position.get("y").and_then(|y| y.as_f64()).unwrap_or(0.0);
+    let mut details = serde_json::json!({ "scrollX": x, "scrollY": y });
+    if let Some(selector) = &opts.selector {
+        details["selector"] = serde_json::json!(selector);
+        details["scrollTop"] = position.get("top").cloned().unwrap_or_default();
+        details["top"] = position.get("rect").cloned().unwrap_or_default();
+    }
+    print_action(
+        json,
+        "scroll",
+        details,
+        &format!("Scrolled (window at {}, {})", x, y),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScrollAlign, ScrollOptions, build_scroll_script};
+
+    fn opts(selector: Option<&str>, into_view: bool, by: Option<f64>) -> ScrollOptions {
+        ScrollOptions {
+            selector: selector.map(str::to_string),
+            into_view,
+            align: ScrollAlign::Start,
+            by,
+            timeout_ms: 2000,
+        }
+    }
+
+    #[test]
+    fn builds_scroll_actions() {
+        let script = build_scroll_script(&opts(Some("#a"), true, None)).unwrap();
+        assert!(script.contains("el.scrollIntoView({ block: 'start'"));
+        assert!(script.contains(r##"document.querySelector("#a")"##));
+
+        let script = build_scroll_script(&opts(Some("#list"), false, Some(200.0))).unwrap();
+        assert!(script.contains("el.scrollBy(0, 200)"));
+
+        let script = build_scroll_script(&opts(None, false, Some(-50.0))).unwrap();
+        assert!(script.contains("window.scrollBy(0, -50)"));
+        assert!(script.contains("const el = null;"));
+
+        assert!(build_scroll_script(&opts(None, false, None)).is_err());
+    }
+}
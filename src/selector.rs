@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::sync::OnceLock;
+
+/// How selector arguments are interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SelectorEngine {
+    /// `xpath=`/`text=`/`css=` prefixes, `//` for XPath, otherwise CSS
+    #[default]
+    Auto,
+    Css,
+    Xpath,
+    /// Innermost elements whose visible text contains the string
+    Text,
+}
+
+static ENGINE: OnceLock<SelectorEngine> = OnceLock::new();
+
+/// Set the engine once at startup from `--selector-engine`; later calls are ignored.
+pub fn set_engine(engine: SelectorEngine) {
+    let _ = ENGINE.set(engine);
+}
+
+fn engine() -> SelectorEngine {
+    ENGINE.get().copied().unwrap_or_default()
+}
+
+static SELECT_TIMEOUT: OnceLock<u64> = OnceLock::new();
+
+/// Set once at startup from `--select-timeout`; later calls are ignored.
+pub fn set_select_timeout(ms: u64) {
+    let _ = SELECT_TIMEOUT.set(ms);
+}
+
+/// Milliseconds an element command gives its target to appear (0: no wait).
+pub(crate) fn select_timeout() -> u64 {
+    SELECT_TIMEOUT.get().copied().unwrap_or(0)
+}
+
+/// Resolve `selector` to a concrete engine and the selector body.
+pub(crate) fn resolve(engine: SelectorEngine, selector: &str) -> (SelectorEngine, &str) {
+    if engine != SelectorEngine::Auto {
+        return (engine, selector);
+    }
+    if let Some(rest) = selector.strip_prefix("xpath=") {
+        (SelectorEngine::Xpath, rest)
+    } else if let Some(rest) = selector.strip_prefix("text=") {
+        (SelectorEngine::Text, rest)
+    } else if let Some(rest) = selector.strip_prefix("css=") {
+        (SelectorEngine::Css, rest)
+    } else if selector.starts_with("//") || selector.starts_with("(//") {
+        (SelectorEngine::Xpath, selector)
+    } else {
+        (SelectorEngine::Css, selector)
+    }
+}
+
+/// JS expression for an array of every element matching `selector`.
+pub(crate) fn all_expr(selector: &str) -> Result<String> {
+    let (engine, body) = resolve(engine(), selector);
+    let quoted = serde_json::to_string(body)?;
+    Ok(match engine {
+        SelectorEngine::Auto | SelectorEngine::Css => {
+            format!("Array.from(document.querySelectorAll({}))", quoted)
+        }
+        SelectorEngine::Xpath => format!(
+            "((r) => Array.from({{ length: r.snapshotLength }}, (_, i) => r.snapshotItem(i)))(document.evaluate({}, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null))",
+            quoted
+        ),
+        SelectorEngine::Text => format!(
+            "((t) => Array.from(document.body.querySelectorAll('*')).filter((el) => (el.innerText || '').includes(t) && !Array.from(el.children).some((c) => (c.innerText || '').includes(t))))({})",
+            quoted
+        ),
+    })
+}
+
+/// JS expression for the first element matching `selector`, or `null`.
+pub(crate) fn first_expr(selector: &str) -> Result<String> {
+    let (engine, body) = resolve(engine(), selector);
+    match engine {
+        SelectorEngine::Auto | SelectorEngine::Css => Ok(format!(
+            "document.querySelector({})",
+            serde_json::to_string(body)?
+        )),
+        _ => Ok(format!("({}[0] ?? null)", all_expr(selector)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SelectorEngine, resolve};
+
+    #[test]
+    fn auto_engine_detects_prefixes() {
+        use SelectorEngine::*;
+        assert_eq!(resolve(Auto, "button.primary"), (Css, "button.primary"));
+        assert_eq!(resolve(Auto, "xpath=//a"), (Xpath, "//a"));
+        assert_eq!(resolve(Auto, "//div[@id='x']"), (Xpath, "//div[@id='x']"));
+        assert_eq!(resolve(Auto, "text=Sign in"), (Text, "Sign in"));
+        assert_eq!(resolve(Auto, "css=text=odd"), (Css, "text=odd"));
+    }
+
+    #[test]
+    fn explicit_engine_skips_detection() {
+        use SelectorEngine::*;
+        assert_eq!(resolve(Css, "//not-xpath"), (Css, "//not-xpath"));
+        assert_eq!(resolve(Text, "xpath=literal"), (Text, "xpath=literal"));
+    }
+}
@@ -12,6 +12,37 @@ pub struct SnapshotOptions {
     pub filter: Option<String>,
     pub full: bool,
     pub mini: bool,
+    pub format: SnapshotFormat,
+    /// Append accessibility warnings after the ARIA tree
+    pub audit: bool,
+    /// Stop emitting node lines after this many
+    pub max_lines: Option<usize>,
+    /// Start the snapshot at the first element matching this selector
+    pub root: Option<String>,
+    /// Order of `--filter` matches
+    pub order: SnapshotOrder,
+    /// Drop `--filter` matches whose rendered subtree repeats an earlier one
+    pub dedup: bool,
+}
+
+/// Order in which `--filter` matches are emitted
+#[derive(Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SnapshotOrder {
+    /// As they appear in the page
+    #[default]
+    Document,
+    /// Last match first
+    Reverse,
+}
+
+/// Output format for DOM snapshots (`--full` / `--mini`)
+#[derive(Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SnapshotFormat {
+    /// Indented dashed outline
+    #[default]
+    Outline,
+    /// Minimal reconstructed HTML
+    Html,
 }
 
 /// A node in the accessibility or React fiber tree
@@ -54,14 +85,23 @@ struct FiberResult {
 /// CDP Accessibility tree node
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct AXNode {
+pub(crate) struct AXNode {
     node_id: String,
+    #[serde(default)]
+    ignored: bool,
     role: Option<AXValue>,
     name: Option<AXValue>,
     #[serde(default)]
+    properties: Vec<AXProperty>,
+    #[serde(default)]
     children: Option<Vec<AXNode>>,
     #[serde(default)]
     child_ids: Vec<String>,
+    #[serde(rename = "backendDOMNodeId")]
+    backend_dom_node_id: Option<i64>,
+    /// Assigned by `assign_ax_refs` for `--interactive` and `--audit` snapshots
+    #[serde(skip)]
+    ref_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -69,6 +109,12 @@ struct AXValue {
     value: Option<serde_json::Value>,
 }
 
+#[derive(Deserialize)]
+struct AXProperty {
+    name: String,
+    value: AXValue,
+}
+
 pub(crate) const INTERACTIVE_ROLES: &[&str] = &[
     "button",
     "link",
@@ -108,6 +154,7 @@ pub async fn take_snapshot(
     cdp: &mut CdpConnection,
     opts: &SnapshotOptions,
 ) -> anyhow::Result<String> {
+    check_options(opts)?;
     if opts.mini {
         take_mini_snapshot(cdp, opts).await
     } else if opts.full {
@@ -119,6 +166,21 @@ pub async fn take_snapshot(
     }
 }
 
+/// Reject flag combinations the chosen snapshot mode would silently ignore.
+pub(crate) fn check_options(opts: &SnapshotOptions) -> anyhow::Result<()> {
+    if opts.format == SnapshotFormat::Html && !opts.mini && !opts.full {
+        anyhow::bail!("--format html requires --full or --mini");
+    }
+    if opts.audit && (opts.mini || opts.full || opts.react) {
+        anyhow::bail!("--audit works on the ARIA snapshot only");
+    }
+    let reorders = opts.order != SnapshotOrder::Document || opts.dedup;
+    if reorders && (!opts.react || opts.mini || opts.full || opts.filter.is_none()) {
+        anyhow::bail!("--order and --dedup apply to --react --filter matches only");
+    }
+    Ok(())
+}
+
 async fn take_aria_snapshot(
     cdp: &mut CdpConnection,
     opts: &SnapshotOptions,
@@ -136,11 +198,26 @@ async fn take_aria_snapshot(
         return Ok("(empty page)".to_string());
     }
 
-    let tree = build_ax_tree(nodes);
+    let mut tree = build_ax_tree(nodes);
+    if let Some(selector) = opts.root.as_deref() {
+        let backend_id = backend_node_id(cdp, selector, None).await?;
+        let node = take_ax_by_backend_id(tree, backend_id)
+            .ok_or_else(|| anyhow::anyhow!("No accessibility node for {}", selector))?;
+        tree = vec![node];
+    }
+    if opts.interactive || opts.audit {
+        let mut refs = std::collections::BTreeMap::new();
+        assign_ax_refs(&mut tree, opts.audit, &mut refs);
+        crate::refs::save_refs(cdp, refs).await?;
+    }
     let mut lines = Vec::new();
     for node in &tree {
         format_ax_node(node, 0, opts, &mut lines);
     }
+    cap_lines(&mut lines, opts);
+    if opts.audit {
+        audit_ax_tree(&tree, &mut lines);
+    }
 
     if lines.is_empty() {
         Ok("(empty page)".to_string())
@@ -186,7 +263,7 @@ fn extract_ax_node(
 }
 
 /// Reconstruct nested tree from flat CDP array using child_ids references.
-fn build_ax_tree(nodes: Vec<AXNode>) -> Vec<AXNode> {
+pub(crate) fn build_ax_tree(nodes: Vec<AXNode>) -> Vec<AXNode> {
     if nodes.is_empty() {
         return vec![];
     }
@@ -201,13 +278,125 @@ fn build_ax_tree(nodes: Vec<AXNode>) -> Vec<AXNode> {
         .collect()
 }
 
+/// `backendNodeId` of the first element matching `selector`.
+async fn backend_node_id(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> anyhow::Result<i64> {
+    let object_id = crate::commands::element_object_id(cdp, selector, nth).await?;
+    let described = cdp
+        .send(
+            "DOM.describeNode",
+            serde_json::json!({ "objectId": object_id }),
+        )
+        .await?;
+    described
+        .pointer("/node/backendNodeId")
+        .and_then(|id| id.as_i64())
+        .ok_or_else(|| anyhow::anyhow!("DOM.describeNode returned no backendNodeId"))
+}
+
+/// States reported by `get aria` when Chrome exposes them for a node.
+const AX_STATE_PROPERTIES: &[&str] = &[
+    "disabled",
+    "checked",
+    "expanded",
+    "pressed",
+    "selected",
+    "required",
+    "focused",
+    "focusable",
+    "readonly",
+    "invalid",
+    "level",
+];
+
+/// Computed role, name and key states for the AX node of a single element.
+pub(crate) async fn describe_ax_element(
+    cdp: &mut CdpConnection,
+    selector: &str,
+    nth: Option<usize>,
+) -> anyhow::Result<serde_json::Value> {
+    let backend_id = backend_node_id(cdp, selector, nth).await?;
+    let result = cdp
+        .send(
+            "Accessibility.getPartialAXTree",
+            serde_json::json!({ "backendNodeId": backend_id, "fetchRelatives": false }),
+        )
+        .await?;
+    let nodes: Vec<AXNode> =
+        serde_json::from_value(result.get("nodes").cloned().unwrap_or_default())?;
+    let node = nodes
+        .into_iter()
+        .find(|n| n.backend_dom_node_id == Some(backend_id))
+        .ok_or_else(|| anyhow::anyhow!("No accessibility node for '{}'", selector))?;
+    Ok(ax_summary(&node))
+}
+
+pub(crate) fn ax_summary(node: &AXNode) -> serde_json::Value {
+    let mut summary = serde_json::json!({
+        "role": ax_value_str(&node.role).unwrap_or_default(),
+        "name": ax_value_str(&node.name).unwrap_or_default(),
+        "ignored": node.ignored,
+    });
+    for prop in &node.properties {
+        if AX_STATE_PROPERTIES.contains(&prop.name.as_str())
+            && let Some(value) = &prop.value.value
+        {
+            summary[prop.name.as_str()] = value.clone();
+        }
+    }
+    summary
+}
+
+/// Detach the subtree for the AX node backed by DOM node `backend_id`.
+pub(crate) fn take_ax_by_backend_id(nodes: Vec<AXNode>, backend_id: i64) -> Option<AXNode> {
+    nodes.into_iter().find_map(|node| {
+        if node.backend_dom_node_id == Some(backend_id) {
+            Some(node)
+        } else {
+            take_ax_by_backend_id(node.children.unwrap_or_default(), backend_id)
+        }
+    })
+}
+
 fn ax_value_str(v: &Option<AXValue>) -> Option<String> {
     v.as_ref()
         .and_then(|av| av.value.as_ref())
         .and_then(|val| val.as_str().map(String::from))
 }
 
-fn format_ax_node(node: &AXNode, depth: usize, opts: &SnapshotOptions, lines: &mut Vec<String>) {
+/// Number interactive nodes e1, e2, ... in document order, recording each ref's
+/// backend DOM node so later commands can act on it with `--ref`. With `audit`,
+/// nodes the audit flags get a ref too, so each warning can name one.
+pub(crate) fn assign_ax_refs(
+    nodes: &mut [AXNode],
+    audit: bool,
+    refs: &mut std::collections::BTreeMap<String, i64>,
+) {
+    for node in nodes {
+        let role = ax_value_str(&node.role).unwrap_or_default();
+        if let Some(backend_id) = node.backend_dom_node_id
+            && !node.ignored
+            && (INTERACTIVE_ROLES.contains(&role.as_str()) || (audit && ax_issue(node).is_some()))
+        {
+            let ref_id = format!("e{}", refs.len() + 1);
+            refs.insert(ref_id.clone(), backend_id);
+            node.ref_id = Some(ref_id);
+        }
+        if let Some(children) = node.children.as_mut() {
+            assign_ax_refs(children, audit, refs);
+        }
+    }
+}
+
+pub(crate) fn format_ax_node(
+    node: &AXNode,
+    depth: usize,
+    opts: &SnapshotOptions,
+    lines: &mut Vec<String>,
+) {
     if max_depth_exceeded(opts, depth) {
         return;
     }
@@ -220,15 +409,96 @@ fn format_ax_node(node: &AXNode, depth: usize, opts: &SnapshotOptions, lines: &m
         return;
     }
 
-    let indent = "  ".repeat(depth);
-    lines.push(format_ax_line(&indent, &role, &name));
+    if line_cap_reached(lines, opts) {
+        lines.push(String::new());
+    } else {
+        let indent = "  ".repeat(depth);
+        let mut line = format_ax_line(&indent, &role, &name);
+        if let Some(ref_id) = &node.ref_id {
+            line.push_str(&format!(" [ref={}]", ref_id));
+        }
+        lines.push(line);
+    }
     visit_ax_children(node, depth + 1, opts, lines);
 }
 
+const FORM_FIELD_ROLES: &[&str] = &[
+    "textbox",
+    "searchbox",
+    "combobox",
+    "listbox",
+    "checkbox",
+    "radio",
+    "slider",
+    "spinbutton",
+    "switch",
+];
+
+/// Append `WARN:` lines for common accessibility problems found in the AX tree,
+/// naming each node by the ref `assign_ax_refs` gave it.
+pub(crate) fn audit_ax_tree(nodes: &[AXNode], lines: &mut Vec<String>) {
+    for node in nodes {
+        if let Some(issue) = ax_issue(node) {
+            let role = ax_value_str(&node.role).unwrap_or_default();
+            match &node.ref_id {
+                Some(ref_id) => lines.push(format!("WARN: {} [ref={}] — {}", role, ref_id, issue)),
+                None => lines.push(format!("WARN: {} — {}", role, issue)),
+            }
+        }
+        if let Some(children) = &node.children {
+            audit_ax_tree(children, lines);
+        }
+    }
+}
+
+fn ax_issue(node: &AXNode) -> Option<&'static str> {
+    if node.ignored {
+        return None;
+    }
+    let role = ax_value_str(&node.role).unwrap_or_default();
+    let unnamed = ax_value_str(&node.name).is_none_or(|name| name.trim().is_empty());
+    match role.as_str() {
+        "image" | "img" if unnamed => Some("image without alt text"),
+        "generic" if ax_property_bool(node, "focusable") => {
+            Some("focusable element with generic role (use a button or link)")
+        }
+        r if unnamed && FORM_FIELD_ROLES.contains(&r) => Some("form field without label"),
+        r if unnamed && INTERACTIVE_ROLES.contains(&r) => Some("missing accessible name"),
+        _ => None,
+    }
+}
+
+fn ax_property_bool(node: &AXNode, name: &str) -> bool {
+    node.properties
+        .iter()
+        .find(|p| p.name == name)
+        .and_then(|p| p.value.value.as_ref())
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
 fn max_depth_exceeded(opts: &SnapshotOptions, depth: usize) -> bool {
     opts.max_depth.is_some_and(|max| depth > max)
 }
 
+/// Once `--max-lines` is reached, formatters push an empty placeholder per node
+/// instead of building its line, so omitted nodes are counted cheaply.
+pub(crate) fn line_cap_reached(lines: &[String], opts: &SnapshotOptions) -> bool {
+    opts.max_lines.is_some_and(|max| lines.len() >= max)
+}
+
+/// Drop placeholder lines past `--max-lines` and append a summary of what was cut.
+pub(crate) fn cap_lines(lines: &mut Vec<String>, opts: &SnapshotOptions) {
+    let Some(max) = opts.max_lines else {
+        return;
+    };
+    if lines.len() > max {
+        let omitted = lines.len() - max;
+        lines.truncate(max);
+        lines.push(format!("... ({} more nodes omitted)", omitted));
+    }
+}
+
 fn should_skip_ax_node(role: &str, name: &str, opts: &SnapshotOptions) -> bool {
     let ignored_role = matches!(role, "none" | "Ignored" | "generic");
     let non_interactive = !INTERACTIVE_ROLES.contains(&role);
@@ -257,7 +527,7 @@ async fn take_react_snapshot(
     opts: &SnapshotOptions,
 ) -> anyhow::Result<String> {
     let js_depth = opts.max_depth.unwrap_or(200);
-    let script = build_fiber_walker_script(js_depth);
+    let script = build_fiber_walker_script(js_depth, opts.root.as_deref())?;
     let result = cdp.eval(&script).await?;
 
     let Some(fiber) = parse_fiber_result(&result) else {
@@ -266,19 +536,23 @@ async fn take_react_snapshot(
     if !fiber.found {
         return take_aria_fallback(cdp, opts).await;
     }
+    if opts.interactive {
+        crate::refs::save_tagged_refs(cdp).await?;
+    }
 
     let mut lines = Vec::new();
     if fiber.all_minified {
         lines.push("# Warning: All component names are minified (production build)".to_string());
     }
 
-    for node in &fiber.tree {
-        if opts.filter.is_some() {
-            collect_filtered_subtrees(node, opts, &mut lines);
-        } else {
+    if opts.filter.is_some() {
+        lines.extend(filtered_lines(&fiber.tree, opts));
+    } else {
+        for node in &fiber.tree {
             format_fiber_node(node, 0, opts, &mut lines);
         }
     }
+    cap_lines(&mut lines, opts);
 
     if lines.is_empty() {
         Ok("(empty)".to_string())
@@ -295,6 +569,9 @@ async fn take_aria_fallback(
     cdp: &mut CdpConnection,
     opts: &SnapshotOptions,
 ) -> anyhow::Result<String> {
+    if opts.order != SnapshotOrder::Document || opts.dedup {
+        anyhow::bail!("No React tree found, so --order and --dedup have no matches to apply to");
+    }
     take_aria_snapshot(
         cdp,
         &SnapshotOptions {
@@ -354,9 +631,13 @@ pub(crate) fn format_fiber_node(
         return;
     }
 
-    let mut line = build_fiber_line(node, depth);
-    format_node_attrs(node, &mut line);
-    lines.push(line);
+    if line_cap_reached(lines, opts) {
+        lines.push(String::new());
+    } else {
+        let mut line = build_fiber_line(node, depth);
+        format_node_attrs(node, &mut line);
+        lines.push(line);
+    }
     for child in &node.children {
         format_fiber_node(child, depth + 1, opts, lines);
     }
@@ -437,54 +718,99 @@ pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
     parts.last().is_some_and(|p| p.is_empty()) || pos == text.len()
 }
 
-/// Walk tree looking for nodes matching filter, output each match as a root subtree
-pub(crate) fn collect_filtered_subtrees(
+/// Render every `--filter` match under `nodes` as a root subtree, honoring
+/// `--order` and `--dedup`.
+pub(crate) fn filtered_lines(nodes: &[TreeNode], opts: &SnapshotOptions) -> Vec<String> {
+    let mut blocks = Vec::new();
+    for node in nodes {
+        collect_filtered_subtrees(node, opts, &mut blocks);
+    }
+    if opts.order == SnapshotOrder::Reverse {
+        blocks.reverse();
+    }
+    if opts.dedup {
+        let mut seen = std::collections::HashSet::new();
+        blocks.retain(|block| seen.insert(block_signature(block)));
+    }
+    blocks.concat()
+}
+
+/// A match's lines with `[ref=…]` markers removed, so repeated components
+/// compare equal even though each element gets its own ref.
+fn block_signature(block: &[String]) -> Vec<String> {
+    block
+        .iter()
+        .map(|line| match line.find(" [ref=") {
+            Some(start) => {
+                let end = line[start..]
+                    .find(']')
+                    .map_or(line.len(), |e| start + e + 1);
+                format!("{}{}", &line[..start], &line[end..])
+            }
+            None => line.clone(),
+        })
+        .collect()
+}
+
+/// Walk tree looking for nodes matching filter, collecting each match's subtree lines
+fn collect_filtered_subtrees(
     node: &TreeNode,
     opts: &SnapshotOptions,
-    lines: &mut Vec<String>,
+    blocks: &mut Vec<Vec<String>>,
 ) {
     let filter = opts.filter.as_deref().unwrap_or("");
     if name_matches_filter(&node.name, filter) {
         let no_filter_opts = SnapshotOptions {
             filter: None,
+            max_lines: None,
             ..opts.clone()
         };
-        format_fiber_node(node, 0, &no_filter_opts, lines);
+        let mut lines = Vec::new();
+        format_fiber_node(node, 0, &no_filter_opts, &mut lines);
+        blocks.push(lines);
     } else {
         for child in &node.children {
-            collect_filtered_subtrees(child, opts, lines);
+            collect_filtered_subtrees(child, opts, blocks);
         }
     }
 }
 
 pub(crate) fn has_interactive_descendant(node: &TreeNode) -> bool {
-    if !node.is_component {
-        if let Some(ref tag) = node.tag {
-            if INTERACTIVE_TAGS.contains(&tag.as_str()) {
-                return true;
-            }
-        }
+    if !node.is_component
+        && let Some(ref tag) = node.tag
+        && INTERACTIVE_TAGS.contains(&tag.as_str())
+    {
+        return true;
     }
     node.children.iter().any(has_interactive_descendant)
 }
 
-fn build_fiber_walker_script(max_depth: usize) -> String {
-    format!(
-        "globalThis.__MAX_DEPTH = {};\n{}",
+fn build_fiber_walker_script(max_depth: usize, root: Option<&str>) -> anyhow::Result<String> {
+    Ok(format!(
+        "globalThis.__MAX_DEPTH = {};\nglobalThis.__ROOT_SELECTOR = {};\nglobalThis.__ROOT_ELEMENT = {};\n{}",
         max_depth,
+        serde_json::to_string(&root)?,
+        match root {
+            Some(selector) => crate::selector::first_expr(selector)?,
+            None => "null".to_string(),
+        },
         include_str!("fiber_walker.js")
-    )
+    ))
 }
 
 async fn take_full_snapshot(
     cdp: &mut CdpConnection,
     opts: &SnapshotOptions,
 ) -> anyhow::Result<String> {
-    let script = build_dom_walker_script();
+    let script = build_dom_walker_script(opts.root.as_deref())?;
     let result = cdp.eval(&script).await?;
     let root: DomNode = serde_json::from_value(result)?;
+    if opts.format == SnapshotFormat::Html {
+        return Ok(render_dom_html(std::slice::from_ref(&root), opts));
+    }
     let mut lines = Vec::new();
     format_dom_node(&root, 0, opts, &mut lines);
+    cap_lines(&mut lines, opts);
     if lines.is_empty() {
         Ok("(empty page)".to_string())
     } else {
@@ -498,10 +824,16 @@ pub(crate) fn format_dom_node(
     opts: &SnapshotOptions,
     lines: &mut Vec<String>,
 ) {
-    if let Some(max) = opts.max_depth {
-        if depth > max {
-            return;
+    if max_depth_exceeded(opts, depth) {
+        return;
+    }
+
+    if line_cap_reached(lines, opts) {
+        lines.push(String::new());
+        for child in &node.children {
+            format_dom_node(child, depth + 1, opts, lines);
         }
+        return;
     }
 
     let indent = "  ".repeat(depth);
@@ -525,8 +857,103 @@ pub(crate) fn format_dom_node(
     }
 }
 
-fn build_dom_walker_script() -> String {
-    r#"(() => {
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+pub(crate) fn render_dom_html(roots: &[DomNode], opts: &SnapshotOptions) -> String {
+    let mut out = String::new();
+    let mut nodes = 0;
+    for root in roots {
+        format_dom_html(root, 0, opts, &mut out, &mut nodes);
+    }
+    if out.is_empty() {
+        return "(empty page)".to_string();
+    }
+    if let Some(max) = opts.max_lines.filter(|max| nodes > *max) {
+        out.push_str(&format!("\n... ({} more nodes omitted)", nodes - max));
+    }
+    out
+}
+
+/// Reconstruct minimal HTML for a DOM node. Fragments emit only their children.
+/// HTML has no line per node, so `--max-lines` caps the elements and text
+/// nodes emitted; later ones are only counted in `nodes`, and open tags are
+/// still closed.
+pub(crate) fn format_dom_html(
+    node: &DomNode,
+    depth: usize,
+    opts: &SnapshotOptions,
+    out: &mut String,
+    nodes: &mut usize,
+) {
+    if max_depth_exceeded(opts, depth) {
+        return;
+    }
+
+    let emit = opts.max_lines.is_none_or(|max| *nodes < max);
+    if let Some(ref text) = node.text {
+        if emit {
+            out.push_str(&escape_html(text, false));
+        }
+        *nodes += 1;
+        return;
+    }
+
+    let Some(tag) = node.tag.as_deref() else {
+        for child in &node.children {
+            format_dom_html(child, depth, opts, out, nodes);
+        }
+        return;
+    };
+
+    *nodes += 1;
+    if emit {
+        out.push('<');
+        out.push_str(tag);
+        for (key, value) in &node.attrs {
+            if let Some(s) = value.as_str() {
+                out.push_str(&format!(" {}=\"{}\"", key, escape_html(s, true)));
+            }
+        }
+        out.push('>');
+    }
+    if VOID_TAGS.contains(&tag) {
+        return;
+    }
+    for child in &node.children {
+        format_dom_html(child, depth + 1, opts, out, nodes);
+    }
+    if emit {
+        out.push_str(&format!("</{}>", tag));
+    }
+}
+
+fn escape_html(text: &str, attr: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attr => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn build_dom_walker_script(root: Option<&str>) -> anyhow::Result<String> {
+    let root = match root {
+        Some(selector) => format!(
+            "({} || (() => {{ throw new Error('Root element not found: ' + {}); }})())",
+            crate::selector::first_expr(selector)?,
+            serde_json::to_string(selector)?
+        ),
+        None => "document.documentElement".to_string(),
+    };
+    Ok(r#"(() => {
   function walk(node) {
     if (node.nodeType === 3) {
       const t = node.textContent.trim();
@@ -550,9 +977,9 @@ fn build_dom_walker_script() -> String {
     }
     return { tag, attrs, children };
   }
-  return walk(document.documentElement);
+  return walk(__ROOT__);
 })()"#
-        .to_string()
+        .replace("__ROOT__", &root))
 }
 
 const STRUCTURAL_TAGS: &[&str] = &[
@@ -617,9 +1044,7 @@ pub(crate) fn flatten_fragments(nodes: Vec<DomNode>) -> Vec<DomNode> {
 pub(crate) fn collapse_dom_tree(node: DomNode) -> Option<DomNode> {
     // Text nodes: keep as-is (they have no children to process)
     if node.tag.is_none() {
-        if node.text.is_none() {
-            return None;
-        }
+        node.text.as_ref()?;
         return Some(node);
     }
 
@@ -666,7 +1091,7 @@ async fn take_mini_snapshot(
     cdp: &mut CdpConnection,
     opts: &SnapshotOptions,
 ) -> anyhow::Result<String> {
-    let script = build_dom_walker_script();
+    let script = build_dom_walker_script(opts.root.as_deref())?;
     let result = cdp.eval(&script).await?;
     let root: DomNode = serde_json::from_value(result)?;
     let collapsed = match collapse_dom_tree(root) {
@@ -679,10 +1104,14 @@ async fn take_mini_snapshot(
     } else {
         vec![collapsed]
     };
+    if opts.format == SnapshotFormat::Html {
+        return Ok(render_dom_html(&roots, opts));
+    }
     let mut lines = Vec::new();
     for root in &roots {
         format_mini_node(root, 0, opts, &mut lines);
     }
+    cap_lines(&mut lines, opts);
     if lines.is_empty() {
         Ok("(empty page)".to_string())
     } else {
@@ -696,10 +1125,23 @@ pub(crate) fn format_mini_node(
     opts: &SnapshotOptions,
     lines: &mut Vec<String>,
 ) {
-    if let Some(max) = opts.max_depth {
-        if depth > max {
-            return;
+    if max_depth_exceeded(opts, depth) {
+        return;
+    }
+
+    // Text promotion below folds a lone text child into its parent's line
+    let promotes_text = node.children.len() == 1
+        && node.children[0].text.is_some()
+        && node.children[0].tag.is_none();
+
+    if line_cap_reached(lines, opts) {
+        lines.push(String::new());
+        if node.text.is_none() && !promotes_text {
+            for child in &node.children {
+                format_mini_node(child, depth + 1, opts, lines);
+            }
         }
+        return;
     }
 
     let indent = "  ".repeat(depth);
@@ -719,14 +1161,13 @@ pub(crate) fn format_mini_node(
     }
 
     // Rule 2: Text promotion — single text child gets inlined
-    if node.children.len() == 1 {
-        if let Some(ref text) = node.children[0].text {
-            if node.children[0].tag.is_none() {
-                line.push_str(&format!(" \"{}\"", text));
-                lines.push(line);
-                return;
-            }
-        }
+    if node.children.len() == 1
+        && let Some(ref text) = node.children[0].text
+        && node.children[0].tag.is_none()
+    {
+        line.push_str(&format!(" \"{}\"", text));
+        lines.push(line);
+        return;
     }
 
     lines.push(line);
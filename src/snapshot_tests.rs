@@ -1,7 +1,9 @@
 use crate::snapshot::{
-    DomNode, SnapshotOptions, TreeNode, collapse_dom_tree, collect_filtered_subtrees,
-    flatten_fragments, format_dom_node, format_fiber_node, format_mini_node, glob_match,
-    has_interactive_descendant,
+    AXNode, DomNode, SnapshotFormat, SnapshotOptions, SnapshotOrder, TreeNode, assign_ax_refs,
+    audit_ax_tree, ax_summary, build_ax_tree, cap_lines, check_options, collapse_dom_tree,
+    filtered_lines, flatten_fragments, format_ax_node, format_dom_html, format_dom_node,
+    format_fiber_node, format_mini_node, glob_match, has_interactive_descendant, render_dom_html,
+    take_ax_by_backend_id,
 };
 
 fn default_opts() -> SnapshotOptions {
@@ -13,6 +15,12 @@ fn default_opts() -> SnapshotOptions {
         filter: None,
         full: false,
         mini: false,
+        format: SnapshotFormat::Outline,
+        audit: false,
+        max_lines: None,
+        root: None,
+        order: SnapshotOrder::Document,
+        dedup: false,
     }
 }
 
@@ -50,13 +58,12 @@ fn make_host(
 }
 
 fn format_tree(nodes: &[TreeNode], opts: &SnapshotOptions) -> Vec<String> {
+    if opts.filter.is_some() {
+        return filtered_lines(nodes, opts);
+    }
     let mut lines = Vec::new();
     for node in nodes {
-        if opts.filter.is_some() {
-            collect_filtered_subtrees(node, opts, &mut lines);
-        } else {
-            format_fiber_node(node, 0, opts, &mut lines);
-        }
+        format_fiber_node(node, 0, opts, &mut lines);
     }
     lines
 }
@@ -206,6 +213,74 @@ fn test_filter_substring() {
     );
 }
 
+#[test]
+fn test_filter_reverse_order_and_dedup() {
+    let card = |label: &str, ref_id: &str| {
+        make_component(
+            "ComicCard",
+            vec![make_host("a", Some(label), Some(ref_id), vec![])],
+        )
+    };
+    let tree = vec![make_component(
+        "App",
+        vec![
+            card("Comic 1", "e1"),
+            card("Comic 2", "e2"),
+            card("Comic 1", "e3"),
+        ],
+    )];
+    let opts = SnapshotOptions {
+        filter: Some("ComicCard".to_string()),
+        order: SnapshotOrder::Reverse,
+        dedup: true,
+        ..default_opts()
+    };
+    assert_eq!(
+        format_tree(&tree, &opts),
+        vec![
+            "- ComicCard",
+            "  - a \"Comic 1\" [ref=e3]",
+            "- ComicCard",
+            "  - a \"Comic 2\" [ref=e2]",
+        ]
+    );
+}
+
+#[test]
+fn test_order_and_dedup_require_react_filter() {
+    let react_filter = SnapshotOptions {
+        react: true,
+        filter: Some("ComicCard".to_string()),
+        dedup: true,
+        ..default_opts()
+    };
+    assert!(check_options(&react_filter).is_ok());
+
+    for opts in [
+        SnapshotOptions {
+            react: false,
+            ..react_filter.clone()
+        },
+        SnapshotOptions {
+            filter: None,
+            ..react_filter.clone()
+        },
+        SnapshotOptions {
+            full: true,
+            ..react_filter.clone()
+        },
+        SnapshotOptions {
+            dedup: false,
+            order: SnapshotOrder::Reverse,
+            react: false,
+            ..react_filter.clone()
+        },
+    ] {
+        let err = check_options(&opts).unwrap_err().to_string();
+        assert!(err.contains("--react --filter"), "{}", err);
+    }
+}
+
 #[test]
 fn test_filter_case_insensitive() {
     let tree = vec![make_component(
@@ -736,3 +811,241 @@ fn test_full_text_as_separate_child() {
     let lines = format_dom(&tree, &default_opts());
     assert_eq!(lines, vec!["- a href=\"/\"", "  - \"Home\""]);
 }
+
+#[test]
+fn test_html_format_basic() {
+    let tree = make_dom_element(
+        "body",
+        vec![],
+        vec![make_dom_element(
+            "a",
+            vec![("href", "/x?a=1&b=\"2\"")],
+            vec![make_dom_text("Fish & <Chips>")],
+        )],
+    );
+    let mut out = String::new();
+    format_dom_html(&tree, 0, &default_opts(), &mut out, &mut 0);
+    assert_eq!(
+        out,
+        "<body><a href=\"/x?a=1&amp;b=&quot;2&quot;\">Fish &amp; &lt;Chips&gt;</a></body>"
+    );
+}
+
+#[test]
+fn test_html_format_void_tags() {
+    let tree = make_dom_element(
+        "form",
+        vec![("action", "/search")],
+        vec![make_dom_element("input", vec![("name", "q")], vec![])],
+    );
+    let mut out = String::new();
+    format_dom_html(&tree, 0, &default_opts(), &mut out, &mut 0);
+    assert_eq!(out, "<form action=\"/search\"><input name=\"q\"></form>");
+}
+
+#[test]
+fn test_html_format_after_mini_collapse() {
+    let tree = make_dom_element(
+        "div",
+        vec![],
+        vec![make_dom_element(
+            "div",
+            vec![],
+            vec![make_dom_element(
+                "button",
+                vec![],
+                vec![make_dom_text("Go")],
+            )],
+        )],
+    );
+    let collapsed = collapse_dom_tree(tree).unwrap();
+    let mut out = String::new();
+    format_dom_html(&collapsed, 0, &default_opts(), &mut out, &mut 0);
+    assert_eq!(out, "<button>Go</button>");
+}
+
+#[test]
+fn test_audit_flags_unnamed_and_generic_nodes() {
+    let nodes: Vec<AXNode> = serde_json::from_value(serde_json::json!([
+        { "nodeId": "1", "role": { "value": "RootWebArea" }, "name": { "value": "Page" },
+          "childIds": ["2", "3", "4", "5", "6", "7", "8"], "backendDOMNodeId": 10 },
+        { "nodeId": "2", "role": { "value": "button" }, "name": { "value": "" },
+          "backendDOMNodeId": 20 },
+        { "nodeId": "3", "role": { "value": "image" }, "backendDOMNodeId": 30 },
+        { "nodeId": "4", "role": { "value": "textbox" }, "name": { "value": "" },
+          "backendDOMNodeId": 40 },
+        { "nodeId": "5", "role": { "value": "generic" }, "backendDOMNodeId": 50,
+          "properties": [{ "name": "focusable", "value": { "value": true } }] },
+        { "nodeId": "6", "role": { "value": "link" }, "name": { "value": "Home" },
+          "backendDOMNodeId": 60 },
+        { "nodeId": "7", "ignored": true, "role": { "value": "button" } },
+        { "nodeId": "8", "role": { "value": "image" } }
+    ]))
+    .unwrap();
+    let mut tree = build_ax_tree(nodes);
+    let mut refs = std::collections::BTreeMap::new();
+    assign_ax_refs(&mut tree, true, &mut refs);
+    let mut lines = Vec::new();
+    audit_ax_tree(&tree, &mut lines);
+    assert_eq!(
+        lines,
+        vec![
+            "WARN: button [ref=e1] — missing accessible name",
+            "WARN: image [ref=e2] — image without alt text",
+            "WARN: textbox [ref=e3] — form field without label",
+            "WARN: generic [ref=e4] — focusable element with generic role (use a button or link)",
+            "WARN: image — image without alt text",
+        ]
+    );
+    assert_eq!(refs.get("e2"), Some(&30));
+    assert_eq!(refs.get("e5"), Some(&60));
+}
+
+#[test]
+fn test_max_lines_counts_omitted_nodes() {
+    let tree = vec![make_component(
+        "App",
+        vec![
+            make_host("button", Some("One"), Some("e1"), vec![]),
+            make_host("button", Some("Two"), Some("e2"), vec![]),
+            make_component(
+                "List",
+                vec![make_host("a", Some("Three"), Some("e3"), vec![])],
+            ),
+        ],
+    )];
+    let opts = SnapshotOptions {
+        max_lines: Some(2),
+        ..default_opts()
+    };
+    let mut lines = format_tree(&tree, &opts);
+    cap_lines(&mut lines, &opts);
+    assert_eq!(
+        lines,
+        vec![
+            "- App",
+            "  - button \"One\" [ref=e1]",
+            "... (3 more nodes omitted)",
+        ]
+    );
+}
+
+#[test]
+fn test_max_lines_in_mini_mode_skips_promoted_text() {
+    let root = make_dom_element(
+        "ul",
+        vec![],
+        vec![
+            make_dom_element("li", vec![], vec![make_dom_text("a")]),
+            make_dom_element("li", vec![], vec![make_dom_text("b")]),
+        ],
+    );
+    let opts = SnapshotOptions {
+        mini: true,
+        max_lines: Some(2),
+        ..default_opts()
+    };
+    let mut lines = Vec::new();
+    format_mini_node(&root, 0, &opts, &mut lines);
+    cap_lines(&mut lines, &opts);
+    assert_eq!(
+        lines,
+        vec!["- ul", "  - li \"a\"", "... (1 more nodes omitted)"]
+    );
+}
+
+#[test]
+fn test_max_lines_caps_html_by_node() {
+    let root = make_dom_element(
+        "body",
+        vec![],
+        vec![
+            make_dom_element("a", vec![("href", "/")], vec![make_dom_text("Home")]),
+            make_dom_element("p", vec![], vec![make_dom_text("Hello")]),
+        ],
+    );
+    let opts = SnapshotOptions {
+        format: SnapshotFormat::Html,
+        full: true,
+        max_lines: Some(2),
+        ..default_opts()
+    };
+    assert_eq!(
+        render_dom_html(&[root], &opts),
+        "<body><a href=\"/\"></a></body>\n... (3 more nodes omitted)"
+    );
+}
+
+#[test]
+fn test_ax_subtree_by_backend_node_id() {
+    let nodes: Vec<AXNode> = serde_json::from_value(serde_json::json!([
+        { "nodeId": "1", "role": { "value": "RootWebArea" }, "name": { "value": "Page" },
+          "childIds": ["2", "4"], "backendDOMNodeId": 10 },
+        { "nodeId": "2", "role": { "value": "navigation" }, "name": { "value": "Main" },
+          "childIds": ["3"], "backendDOMNodeId": 20 },
+        { "nodeId": "3", "role": { "value": "link" }, "name": { "value": "Home" },
+          "backendDOMNodeId": 30 },
+        { "nodeId": "4", "role": { "value": "button" }, "name": { "value": "Buy" },
+          "backendDOMNodeId": 40 }
+    ]))
+    .unwrap();
+    let subtree = take_ax_by_backend_id(build_ax_tree(nodes), 20).unwrap();
+    let mut lines = Vec::new();
+    format_ax_node(&subtree, 0, &default_opts(), &mut lines);
+    assert_eq!(lines, vec!["- navigation \"Main\"", "  - link \"Home\""]);
+}
+
+#[test]
+fn test_ax_summary_reports_role_name_and_states() {
+    let node: AXNode = serde_json::from_value(serde_json::json!({
+        "nodeId": "7", "role": { "value": "checkbox" }, "name": { "value": "Remember me" },
+        "properties": [
+            { "name": "checked", "value": { "type": "tristate", "value": "true" } },
+            { "name": "disabled", "value": { "type": "boolean", "value": true } },
+            { "name": "hiddenRoot", "value": { "type": "boolean", "value": false } }
+        ],
+        "backendDOMNodeId": 12
+    }))
+    .unwrap();
+    let summary = ax_summary(&node);
+    assert_eq!(summary["role"], "checkbox");
+    assert_eq!(summary["name"], "Remember me");
+    assert_eq!(summary["checked"], "true");
+    assert_eq!(summary["disabled"], true);
+    assert!(summary.get("hiddenRoot").is_none());
+}
+
+#[test]
+fn test_interactive_ax_snapshot_assigns_refs() {
+    let nodes: Vec<AXNode> = serde_json::from_value(serde_json::json!([
+        { "nodeId": "1", "role": { "value": "RootWebArea" }, "name": { "value": "Page" },
+          "childIds": ["2", "3", "4"], "backendDOMNodeId": 10 },
+        { "nodeId": "2", "role": { "value": "heading" }, "name": { "value": "Login" },
+          "backendDOMNodeId": 20 },
+        { "nodeId": "3", "role": { "value": "textbox" }, "name": { "value": "Email" },
+          "backendDOMNodeId": 30 },
+        { "nodeId": "4", "role": { "value": "button" }, "name": { "value": "Sign in" },
+          "backendDOMNodeId": 40 }
+    ]))
+    .unwrap();
+    let mut tree = build_ax_tree(nodes);
+    let mut refs = std::collections::BTreeMap::new();
+    assign_ax_refs(&mut tree, false, &mut refs);
+    let opts = SnapshotOptions {
+        interactive: true,
+        ..default_opts()
+    };
+    let mut lines = Vec::new();
+    for node in &tree {
+        format_ax_node(node, 0, &opts, &mut lines);
+    }
+    assert_eq!(
+        lines,
+        vec![
+            "- textbox \"Email\" [ref=e1]",
+            "- button \"Sign in\" [ref=e2]"
+        ]
+    );
+    assert_eq!(refs.get("e1"), Some(&30));
+    assert_eq!(refs.get("e2"), Some(&40));
+}
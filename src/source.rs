@@ -0,0 +1,114 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use tokio::time::{Duration, Instant, timeout};
+
+use crate::cdp::{self, CdpConnection};
+use crate::errors::ErrorKind;
+
+/// How long to wait for the reloaded document to finish loading
+const SOURCE_CAPTURE_TIMEOUT_MS: u64 = 15000;
+
+pub async fn cmd_source(port: u16) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    let body = match capture_document_body(&mut cdp).await {
+        Ok(body) => body,
+        Err(err) => {
+            eprintln!(
+                "Could not capture response body ({}), fetching over HTTP",
+                err
+            );
+            fetch_with_cookies(&mut cdp).await?
+        }
+    };
+    println!("{}", body);
+    Ok(())
+}
+
+/// Reload with the Network domain enabled and return the main document's response body.
+async fn capture_document_body(cdp: &mut CdpConnection) -> Result<String> {
+    cdp.send("Network.enable", serde_json::json!({})).await?;
+    cdp.send("Page.reload", serde_json::json!({})).await?;
+
+    let deadline = Instant::now() + Duration::from_millis(SOURCE_CAPTURE_TIMEOUT_MS);
+    let mut request_id: Option<String> = None;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Some(message) = timeout(remaining, cdp.recv())
+            .await
+            .map_err(|_| ErrorKind::Timeout.error("Timeout: no document response"))??
+        else {
+            return Err(ErrorKind::Connection
+                .error("Connection closed while capturing source")
+                .into());
+        };
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let event_request_id = params.get("requestId").and_then(Value::as_str);
+        match method {
+            "Network.responseReceived"
+                if request_id.is_none()
+                    && params.get("type").and_then(Value::as_str) == Some("Document") =>
+            {
+                request_id = event_request_id.map(String::from);
+            }
+            "Network.loadingFinished" if request_id.as_deref() == event_request_id => {
+                break;
+            }
+            "Network.loadingFailed" if request_id.as_deref() == event_request_id => {
+                return Err(anyhow!("document request failed"));
+            }
+            _ => {}
+        }
+    }
+
+    let request_id = request_id.context("no document response seen")?;
+    let result = cdp
+        .send(
+            "Network.getResponseBody",
+            serde_json::json!({ "requestId": request_id }),
+        )
+        .await?;
+    let body = result
+        .get("body")
+        .and_then(Value::as_str)
+        .context("Network.getResponseBody returned no body")?;
+    if result.get("base64Encoded").and_then(Value::as_bool) == Some(true) {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD.decode(body)?;
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Ok(body.to_string())
+}
+
+async fn fetch_with_cookies(cdp: &mut CdpConnection) -> Result<String> {
+    let url = cdp.eval("window.location.href").await?;
+    let url = url.as_str().context("Could not read page URL")?.to_string();
+    let cookies = cdp
+        .send("Network.getCookies", serde_json::json!({ "urls": [url] }))
+        .await?;
+    let cookie_header = cookies
+        .get("cookies")
+        .and_then(Value::as_array)
+        .map(|cookies| {
+            cookies
+                .iter()
+                .filter_map(|c| {
+                    let name = c.get("name")?.as_str()?;
+                    let value = c.get("value")?.as_str()?;
+                    Some(format!("{}={}", name, value))
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default();
+
+    let mut request = reqwest::Client::new().get(&url);
+    if !cookie_header.is_empty() {
+        request = request.header(reqwest::header::COOKIE, cookie_header);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| ErrorKind::Other.error(format!("Failed to fetch {}", url)))?;
+    Ok(response.text().await?)
+}
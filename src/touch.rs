@@ -0,0 +1,191 @@
+use anyhow::{Result, anyhow};
+
+use crate::cdp::{self, CdpConnection};
+use crate::output::print_action;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SwipeDirection {
+    fn offset(self, distance: f64) -> (f64, f64) {
+        match self {
+            Self::Left => (-distance, 0.0),
+            Self::Right => (distance, 0.0),
+            Self::Up => (0.0, -distance),
+            Self::Down => (0.0, distance),
+        }
+    }
+}
+
+/// Parse an `x,y` coordinate pair.
+pub fn parse_point(value: &str) -> Result<(f64, f64), String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected x,y but got '{}'", value))?;
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid coordinate '{}'", n))
+    };
+    Ok((parse(x)?, parse(y)?))
+}
+
+/// Intermediate points from `from` (exclusive) to `to` (inclusive).
+pub(crate) fn swipe_path(from: (f64, f64), to: (f64, f64), steps: u32) -> Vec<(f64, f64)> {
+    let steps = steps.max(1);
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+        })
+        .collect()
+}
+
+const ELEMENT_CENTER_SCRIPT_TEMPLATE: &str = r#"(() => {
+    const el = __ELEMENT__;
+    if (!el) throw new Error('Element not found');
+    el.scrollIntoView({ block: 'center', inline: 'center' });
+    const r = el.getBoundingClientRect();
+    return { x: r.left + r.width / 2, y: r.top + r.height / 2 };
+})()"#;
+
+/// Viewport-relative center of the first element matching `selector`, scrolled into view.
+pub(crate) async fn element_center(cdp: &mut CdpConnection, selector: &str) -> Result<(f64, f64)> {
+    crate::commands::await_element(cdp, selector, None).await?;
+    let script = ELEMENT_CENTER_SCRIPT_TEMPLATE.replace(
+        "__ELEMENT__",
+        &crate::commands::element_expr(selector, None)?,
+    );
+    let point = cdp.eval(&script).await?;
+    let x = point.get("x").and_then(|x| x.as_f64());
+    let y = point.get("y").and_then(|y| y.as_f64());
+    match (x, y) {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(anyhow!("Element not found: {}", selector)),
+    }
+}
+
+async fn enable_touch(cdp: &mut CdpConnection) -> Result<()> {
+    cdp.send(
+        "Emulation.setTouchEmulationEnabled",
+        serde_json::json!({ "enabled": true, "maxTouchPoints": 1 }),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn dispatch_touch(
+    cdp: &mut CdpConnection,
+    kind: &str,
+    point: Option<(f64, f64)>,
+) -> Result<()> {
+    let touch_points = match point {
+        Some((x, y)) => serde_json::json!([{ "x": x, "y": y }]),
+        None => serde_json::json!([]),
+    };
+    cdp.send(
+        "Input.dispatchTouchEvent",
+        serde_json::json!({ "type": kind, "touchPoints": touch_points }),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn cmd_tap(port: u16, selector: &str, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    enable_touch(&mut cdp).await?;
+    let point = element_center(&mut cdp, selector).await?;
+    dispatch_touch(&mut cdp, "touchStart", Some(point)).await?;
+    dispatch_touch(&mut cdp, "touchEnd", None).await?;
+    print_action(
+        json,
+        "tap",
+        serde_json::json!({ "selector": selector }),
+        "Tapped",
+    );
+    Ok(())
+}
+
+/// Swipe endpoints and step count for `cmd_swipe`
+pub struct SwipeOptions {
+    pub direction: Option<SwipeDirection>,
+    pub from: Option<(f64, f64)>,
+    pub to: Option<(f64, f64)>,
+    pub selector: Option<String>,
+    pub distance: f64,
+    pub steps: u32,
+}
+
+pub async fn cmd_swipe(port: u16, opts: &SwipeOptions, json: bool) -> Result<()> {
+    let mut cdp = cdp::connect_active(port).await?;
+    enable_touch(&mut cdp).await?;
+
+    let from = match (opts.from, opts.selector.as_deref()) {
+        (Some(point), _) => point,
+        (None, Some(selector)) => element_center(&mut cdp, selector).await?,
+        (None, None) => viewport_center(&mut cdp).await?,
+    };
+    let to = match (opts.to, opts.direction) {
+        (Some(point), _) => point,
+        (None, Some(direction)) => {
+            let (dx, dy) = direction.offset(opts.distance);
+            (from.0 + dx, from.1 + dy)
+        }
+        (None, None) => return Err(anyhow!("Give a direction or --to x,y")),
+    };
+
+    dispatch_touch(&mut cdp, "touchStart", Some(from)).await?;
+    for point in swipe_path(from, to, opts.steps) {
+        dispatch_touch(&mut cdp, "touchMove", Some(point)).await?;
+    }
+    dispatch_touch(&mut cdp, "touchEnd", None).await?;
+    print_action(
+        json,
+        "swipe",
+        serde_json::json!({ "from": [from.0, from.1], "to": [to.0, to.1] }),
+        "Swiped",
+    );
+    Ok(())
+}
+
+async fn viewport_center(cdp: &mut CdpConnection) -> Result<(f64, f64)> {
+    let size = cdp
+        .eval("({ x: window.innerWidth / 2, y: window.innerHeight / 2 })")
+        .await?;
+    let x = size.get("x").and_then(|x| x.as_f64()).unwrap_or(0.0);
+    let y = size.get("y").and_then(|y| y.as_f64()).unwrap_or(0.0);
+    Ok((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_point, swipe_path};
+
+    #[test]
+    fn parses_coordinate_pairs() {
+        assert_eq!(parse_point("10,20.5"), Ok((10.0, 20.5)));
+        assert_eq!(parse_point(" 3 , 4 "), Ok((3.0, 4.0)));
+        assert!(parse_point("10").is_err());
+        assert!(parse_point("a,b").is_err());
+    }
+
+    #[test]
+    fn swipe_path_interpolates_to_target() {
+        let path = swipe_path((0.0, 100.0), (200.0, 100.0), 4);
+
+        assert_eq!(
+            path,
+            vec![
+                (50.0, 100.0),
+                (100.0, 100.0),
+                (150.0, 100.0),
+                (200.0, 100.0)
+            ]
+        );
+    }
+}
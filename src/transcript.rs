@@ -0,0 +1,150 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+
+use crate::output::print_action;
+
+/// Command-line arguments of this invocation without the program name and the
+/// `--transcript` option, so a replayed command doesn't record itself again.
+pub fn recorded_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--transcript" {
+            args.next();
+        } else if !arg.starts_with("--transcript=") {
+            out.push(arg);
+        }
+    }
+    out
+}
+
+/// One transcript line: what ran, when, for how long, and how it ended.
+pub fn entry(
+    command: &str,
+    args: &[String],
+    timestamp_ms: u128,
+    duration_ms: u128,
+    error: Option<(crate::errors::ErrorKind, String)>,
+) -> Value {
+    let mut entry = serde_json::json!({
+        "timestamp": timestamp_ms as u64,
+        "command": command,
+        "args": args,
+        "durationMs": duration_ms as u64,
+        "ok": error.is_none(),
+    });
+    if let Some((kind, message)) = error {
+        entry["error"] = serde_json::json!({ "kind": kind.as_str(), "message": message });
+    }
+    entry
+}
+
+/// Append `entry` as one line, creating the file and its directory if needed.
+pub fn append(path: &Path, entry: &Value) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open transcript {}", path.display()))?;
+    writeln!(file, "{}", entry)
+        .with_context(|| format!("Failed to write transcript {}", path.display()))
+}
+
+/// Re-run each recorded command in order as a separate invocation of this
+/// binary, with its original global flags. Stops at the first failure unless
+/// `keep_going` is set.
+pub async fn cmd_replay(path: &Path, keep_going: bool, json: bool) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+    let exe = std::env::current_exe().context("Cannot locate the browser-cli executable")?;
+    let mut ran = 0;
+    let mut failed = 0;
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+    {
+        let entry: Value = serde_json::from_str(line).with_context(|| {
+            format!(
+                "{}:{} is not a transcript entry",
+                path.display(),
+                number + 1
+            )
+        })?;
+        if entry.get("command").and_then(Value::as_str) == Some("replay") {
+            continue;
+        }
+        let args: Vec<String> = entry
+            .get("args")
+            .and_then(Value::as_array)
+            .with_context(|| format!("{}:{} has no args", path.display(), number + 1))?
+            .iter()
+            .filter_map(|arg| arg.as_str().map(str::to_string))
+            .collect();
+        eprintln!("▶ browser-cli {}", args.join(" "));
+        let status = tokio::process::Command::new(&exe)
+            .args(&args)
+            .status()
+            .await
+            .context("Failed to run browser-cli")?;
+        ran += 1;
+        if status.success() {
+            continue;
+        }
+        failed += 1;
+        if !keep_going {
+            return Err(anyhow!(
+                "Replay stopped at line {}: `browser-cli {}` failed ({})",
+                number + 1,
+                args.join(" "),
+                status
+            ));
+        }
+    }
+    print_action(
+        json,
+        "replay",
+        serde_json::json!({ "commands": ran, "failed": failed }),
+        &format!("Replayed {} commands ({} failed)", ran, failed),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entry, recorded_args};
+    use crate::errors::ErrorKind;
+
+    #[test]
+    fn recorded_args_drop_transcript_option() {
+        let args = ["--transcript", "/tmp/t.jsonl", "--json", "click", "#go"].map(String::from);
+        assert_eq!(recorded_args(args), ["--json", "click", "#go"]);
+
+        let args = ["--transcript=/tmp/t.jsonl", "get", "title"].map(String::from);
+        assert_eq!(recorded_args(args), ["get", "title"]);
+    }
+
+    #[test]
+    fn entry_records_failures_with_kind() {
+        let args = vec!["click".to_string(), "#missing".to_string()];
+        let line = entry(
+            "click",
+            &args,
+            1700000000000,
+            42,
+            Some((ErrorKind::ElementNotFound, "Element not found".to_string())),
+        );
+
+        assert_eq!(line["ok"], false);
+        assert_eq!(line["args"][1], "#missing");
+        assert_eq!(line["error"]["kind"], "element_not_found");
+        assert_eq!(line["durationMs"], 42);
+    }
+}
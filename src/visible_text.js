@@ -0,0 +1,39 @@
+((root) => {
+    if (!root) return '';
+    const SKIP = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE']);
+    const shown = new Map();
+
+    function visible(el) {
+      if (shown.has(el)) return shown.get(el);
+      const style = getComputedStyle(el);
+      const rect = el.getBoundingClientRect();
+      // Screen-reader-only text is clipped to a 1px box, so require more than that.
+      const result = !el.closest('[aria-hidden="true"]')
+        && (el.checkVisibility ? el.checkVisibility({ visibilityProperty: true }) : style.display !== 'none')
+        && style.visibility !== 'hidden'
+        && rect.width > 1 && rect.height > 1;
+      shown.set(el, result);
+      return result;
+    }
+
+    function blockOf(el) {
+      while (el !== root && el.parentElement && getComputedStyle(el).display.startsWith('inline')) {
+        el = el.parentElement;
+      }
+      return el;
+    }
+
+    const walker = document.createTreeWalker(root, NodeFilter.SHOW_TEXT);
+    let out = '';
+    let lastBlock = null;
+    for (let node = walker.nextNode(); node; node = walker.nextNode()) {
+      const el = node.parentElement;
+      const text = node.nodeValue.replace(/\s+/g, ' ').trim();
+      if (!text || !el || SKIP.has(el.tagName) || !visible(el)) continue;
+      const block = blockOf(el);
+      if (out) out += block === lastBlock ? ' ' : '\n';
+      out += text;
+      lastBlock = block;
+    }
+    return out;
+})(__ROOT__)